@@ -0,0 +1,359 @@
+//! Smooth edges for the globe, and a glow around it
+
+use std::f64::consts::PI;
+
+use {Float, Interpolate, OrthoProj, Projection, View};
+
+/// Pixels on the edge of the globe are split into this many parts across & down, to work out how
+/// much of them is on the globe
+const SUBSAMPLES: u32 = 8;
+
+impl<T: Interpolate> OrthoProj<T> {
+    /// Like `new_with_bg`, but pixels on the edge of the globe are a mix of `bg` & `surface`,
+    /// depending on how much of the pixel is on the globe, so the edge isn't jagged.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///let image = OrthoProj::new_with_bg_antialiased(100, 0., 0., [0u8, 0, 0], [0, 0, 255]);
+    ///assert_eq!(image.get_pixel(50, 50), &[0, 0, 255]);
+    ///assert_eq!(image.get_pixel(0, 0), &[0, 0, 0]);
+    ///// Part way along the edge
+    ///assert!((1..255).contains(&image.get_pixel(0, 43)[2]));
+    ///```
+    pub fn new_with_bg_antialiased<F: Float>(size: u32, lat: F, lon: F, bg: T, surface: T) -> Self {
+        let mut o = Self::new(size, lat, lon, bg);
+        o.fill_globe_antialiased(surface);
+        o
+    }
+}
+
+impl<T: Interpolate, P: Projection> OrthoProj<T, P> {
+    /// Set every pixel on the globe to `surface`, like `fill_globe`, but mix it with the
+    /// current value of pixels on the edge, by how much of the pixel is on the globe. Pixels
+    /// entirely off the globe are unchanged.
+    pub fn fill_globe_antialiased(&mut self, surface: T) {
+        if self._data.is_empty() || !self._view.is_finite() {
+            return;
+        }
+        let edge = self._view.edge();
+        let view = &self._view;
+        for (y, row) in self._data.chunks_mut(self._width as usize).enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                let c = view.coverage(&edge, x as u32, y as u32);
+                if c >= 1. {
+                    *value = surface.clone();
+                } else if c > 0. {
+                    *value = T::weighted_sum(&[(surface.clone(), c), (value.clone(), 1. - c)]);
+                }
+            }
+        }
+    }
+
+    /// Draw a glow around the globe, like the atmosphere, `width` pixels wide. Just off the edge
+    /// it's `colour`, and it fades out into the current pixels further away. Draw this after
+    /// the globe, since it only changes the part of each pixel which is off the globe.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///let mut image = OrthoProj::new(100, 0., 0., [0u8, 0, 0]).with_radius(40.);
+    ///image.fill_globe_antialiased([0, 0, 255]);
+    ///image.draw_atmosphere([128, 192, 255], 8.);
+    ///assert_eq!(image.get_pixel(50, 50), &[0, 0, 255]);
+    ///assert!(image.get_pixel(50, 8)[0] > 50);
+    ///assert_eq!(image.get_pixel(0, 0), &[0, 0, 0]);
+    ///```
+    pub fn draw_atmosphere<F: Float>(&mut self, colour: T, width: F) {
+        let width = width.to_f64();
+        if width <= 0. || width.is_nan() || self._data.is_empty() || !self._view.is_finite() {
+            return;
+        }
+        let edge = self._view.edge();
+        let view = &self._view;
+        for (y, row) in self._data.chunks_mut(self._width as usize).enumerate() {
+            let dy = y as f64 + 0.5 - view.cy;
+            for (x, value) in row.iter_mut().enumerate() {
+                let dx = x as f64 + 0.5 - view.cx;
+                let d = dx.hypot(dy);
+                // Too far from any of the edge
+                if d - edge.max >= width {
+                    continue;
+                }
+                let coverage = view.coverage(&edge, x as u32, y as u32);
+                if coverage >= 1. {
+                    continue;
+                }
+                let distance = (d - edge_radius(&edge.radii, dy.atan2(dx))).max(0.);
+                if distance >= width {
+                    continue;
+                }
+                let glow = (1. - distance / width).powi(2) * (1. - coverage);
+                *value = T::weighted_sum(&[(colour.clone(), glow), (value.clone(), 1. - glow)]);
+            }
+        }
+    }
+}
+
+/// The edge of the map on the image, to quickly tell which pixels are all on the map, all off
+/// it, or partly on it
+struct Edge {
+    /// How far the edge is from the centre (in pixels), going around it, as (angle, distance),
+    /// sorted by angle. Every map's edge only goes around the centre once.
+    radii: Vec<(f64, f64)>,
+    /// The closest & furthest the edge is from the centre
+    min: f64,
+    max: f64,
+    /// Pixels whose centre is more than this (in pixels) nearer or further from the centre than
+    /// the edge are all on one side of it
+    band: f64,
+}
+
+impl Edge {
+    /// Whether the pixel whose centre is `x`, `y` from the centre of the map is all on the map
+    /// (`Some(true)`), all off it (`Some(false)`), or might be partly on it (`None`)
+    fn side(&self, x: f64, y: f64) -> Option<bool> {
+        let d = x.hypot(y);
+        if d < self.min - self.band {
+            return Some(true);
+        }
+        if d > self.max + self.band {
+            return Some(false);
+        }
+        let r = edge_radius(&self.radii, y.atan2(x));
+        if d < r - self.band {
+            Some(true)
+        } else if d > r + self.band {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+impl<P: Projection> View<P> {
+    /// Whether the centre & rotation are numbers. Nothing is on the image if they aren't, e.g.
+    /// after `with_offset(f64::NAN, 0.)`.
+    fn is_finite(&self) -> bool {
+        self.cx.is_finite() && self.cy.is_finite() && self.rotation.0.is_finite() && self.rotation.1.is_finite()
+    }
+
+    /// Where the edge of the map is, with points about a pixel apart
+    fn edge(&self) -> Edge {
+        let steps = (self.edge_length(2. * PI).ceil() as usize).clamp(1024, 1 << 20);
+        let points: Vec<_> = (0..steps).map(|i| {
+            let (x, y) = self.xy_for_vec(self.edge_point(i as f64 * 2. * PI / steps as f64));
+            (x - self.cx, y - self.cy)
+        }).collect();
+
+        // The edge can be up to about `gap` from the straight lines between the points, and where
+        // it slants across the way out from the centre, it's further from them going that way
+        let (mut gap, mut slant): (f64, f64) = (0., 1.);
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % steps];
+            let (tx, ty) = (b.0 - a.0, b.1 - a.1);
+            let (mx, my) = ((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+            let (length, distance) = (tx.hypot(ty), mx.hypot(my));
+            gap = gap.max(length);
+            if length > 0. && distance > 0. {
+                let sin = (mx * ty - my * tx).abs() / (length * distance);
+                slant = slant.max(1. / sin.max(0.1));
+            }
+        }
+
+        let mut radii: Vec<_> = points.iter().map(|&(x, y)| (y.atan2(x), x.hypot(y))).collect();
+        radii.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let min = radii.iter().fold(f64::INFINITY, |min, r| min.min(r.1));
+        let max = radii.iter().fold(0., |max: f64, r| max.max(r.1));
+        Edge{ radii, min, max, band: (0.5f64.sqrt() + gap) * slant }
+    }
+
+    /// How much of pixel `x`, `y` (from 0 to 1) is on the map
+    fn coverage(&self, edge: &Edge, x: u32, y: u32) -> f64 {
+        let (x, y) = (x as f64, y as f64);
+        match edge.side(x + 0.5 - self.cx, y + 0.5 - self.cy) {
+            Some(true) => 1.,
+            Some(false) => 0.,
+            None => {
+                // Part of this pixel might be on the map, so look at lots of points in it
+                let n = SUBSAMPLES;
+                let mut count = 0;
+                for j in 0..n {
+                    for i in 0..n {
+                        let (dx, dy) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                        if self.pos_for_xy(x + dx, y + dy).is_some() {
+                            count += 1;
+                        }
+                    }
+                }
+                count as f64 / (n * n) as f64
+            },
+        }
+    }
+}
+
+/// How far the edge is from the centre at `angle`, from `Edge::radii`, in between the closest
+/// angles either side.
+fn edge_radius(edge: &[(f64, f64)], angle: f64) -> f64 {
+    let i = edge.partition_point(|&(a, _)| a < angle);
+    // Going around past ±180°
+    let (before, after) = match i {
+        0 => ((edge[edge.len()-1].0 - 2. * PI, edge[edge.len()-1].1), edge[0]),
+        i if i == edge.len() => (edge[i-1], (edge[0].0 + 2. * PI, edge[0].1)),
+        i => (edge[i-1], edge[i]),
+    };
+    if after.0 <= before.0 {
+        return before.1;
+    }
+    let t = (angle - before.0) / (after.0 - before.0);
+    before.1 + t * (after.1 - before.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Ellipsoid, Mollweide, Orthographic, PlateCarree, Robinson, Stereographic};
+
+    #[test]
+    fn test_antialiased() {
+        let o = OrthoProj::new_with_bg_antialiased(200, 0., 0., 0., 1.);
+        // The whole globe is there, unlike adding up whole pixels
+        let area: f64 = o.iter().sum();
+        assert!((area - PI * 100. * 100.).abs() < 1., "{}", area);
+        let hard = OrthoProj::new_with_bg(200, 0., 0., 0., 1.);
+        let hard_area: f64 = hard.iter().sum();
+        assert!((hard_area - PI * 100. * 100.).abs() > 1.);
+
+        // Only the edge is changed
+        let mut edge = 0;
+        for (x, y, &v) in o.enumerate_pixels() {
+            let r = (x as f64 + 0.5 - 100.).hypot(y as f64 + 0.5 - 100.);
+            if r < 99.2 {
+                assert_eq!(v, 1.);
+            } else if r > 100.8 {
+                assert_eq!(v, 0.);
+            } else if v > 0. && v < 1. {
+                edge += 1;
+                // About the right amount of the pixel is on the globe
+                assert!((v - (100.5 - r)).abs() < 0.3, "({}, {}) {} {}", x, y, r, v);
+            }
+        }
+        assert!(edge > 500, "{}", edge);
+
+        // Mixed with what's already there
+        let mut o = OrthoProj::new_with_projection(100, 100, Stereographic::new(0., 0.), [200u8, 0]);
+        o.fill_globe_antialiased([0, 100]);
+        let (x, y) = o.xy_for_pos(0., 0.).unwrap();
+        assert_eq!(o.get_pixel(x, y), &[0, 100]);
+        let mixed: Vec<_> = o.iter().filter(|v| v[0] > 0 && v[0] < 200).collect();
+        assert!(mixed.len() > 50);
+        for v in mixed {
+            // c * [0, 100] + (1 - c) * [200, 0]
+            assert!((v[0] as i32 + v[1] as i32 * 2 - 200).abs() <= 2, "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn test_atmosphere() {
+        let mut o = OrthoProj::new(200, 0., 0., 0.).with_radius(80.);
+        o.fill_globe_antialiased(1.);
+        o.draw_atmosphere(0.5, 10.);
+        // Fades out from the edge
+        let v = |x: u32| *o.get_pixel(x, 100);
+        assert_eq!(v(100), 1.);
+        assert_eq!(v(175), 1.);
+        assert!((v(181) - 0.5 * 0.85f64.powi(2)).abs() < 0.01, "{}", v(181));
+        assert!(v(181) > v(185) && v(185) > v(188) && v(188) > 0.);
+        assert_eq!(v(191), 0.);
+        assert_eq!(v(199), 0.);
+        // The same all the way around
+        assert!((*o.get_pixel(100, 184) - v(184)).abs() < 0.01);
+        let distance = 59.5f64.hypot(59.5) - 80.;
+        assert!((*o.get_pixel(40, 40) - 0.5 * (1. - distance / 10.).powi(2)).abs() < 0.01);
+
+        // Around the edge of other shapes too
+        let mut o = OrthoProj::new_with_projection(400, 200, Mollweide::new(0.), 0.).with_radius(60.);
+        o.fill_globe_antialiased(1.);
+        o.draw_atmosphere(1., 20.);
+        assert_eq!(o.get_pixel(200, 100), &1.);
+        assert_eq!(o.get_pixel(0, 0), &0.);
+        // About 10 pixels off the left & top
+        for &(x, y) in &[(20, 100), (200, 5)] {
+            let v = *o.get_pixel(x, y);
+            assert!(v > 0.15 && v < 0.35, "({}, {}) {}", x, y, v);
+        }
+    }
+
+    #[test]
+    fn test_not_finite() {
+        // Nothing is drawn, like everything else, rather than panicking
+        let views = vec![
+            OrthoProj::new(100, 0., 0., 0.).with_rotation(f64::NAN),
+            OrthoProj::new(100, 0., 0., 0.).with_offset(f64::NAN, 0.),
+            OrthoProj::new(100, 0., 0., 0.).with_offset(f64::INFINITY, 0.),
+            OrthoProj::new(100, 0., 0., 0.).with_offset(0., f64::NEG_INFINITY),
+        ];
+        for mut o in views {
+            o.fill_globe_antialiased(1.);
+            o.draw_atmosphere(1., 10.);
+            assert!(o.iter().all(|&v| v == 0.));
+        }
+
+        // A NaN width doesn't do anything either
+        let mut o = OrthoProj::new(100, 0., 0., 0.).with_radius(40.);
+        o.draw_atmosphere(1., f64::NAN);
+        assert!(o.iter().all(|&v| v == 0.));
+    }
+
+    /// How much of each pixel is on the map, looking at lots of points in every pixel
+    fn brute_force<P: Projection>(o: &OrthoProj<f64, P>, x: u32, y: u32) -> f64 {
+        let n = SUBSAMPLES;
+        let mut count = 0;
+        for j in 0..n {
+            for i in 0..n {
+                let (dx, dy) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                if o._view.pos_for_xy(x as f64 + dx, y as f64 + dy).is_some() {
+                    count += 1;
+                }
+            }
+        }
+        count as f64 / (n * n) as f64
+    }
+
+    fn check_coverage<P: Projection>(o: OrthoProj<f64, P>) {
+        let edge = o._view.edge();
+        let mut subsampled = 0;
+        for (x, y, _) in o.enumerate_pixels() {
+            let (dx, dy) = (x as f64 + 0.5 - o._view.cx, y as f64 + 0.5 - o._view.cy);
+            if edge.side(dx, dy).is_none() {
+                subsampled += 1;
+            }
+            // Far from the edge, it's clearly on or off the map, so only look closely nearby
+            if (dx.hypot(dy) - edge_radius(&edge.radii, dy.atan2(dx))).abs() < edge.band + 3. {
+                assert_eq!(o._view.coverage(&edge, x, y), brute_force(&o, x, y), "({}, {})", x, y);
+            }
+        }
+        // Only pixels near the edge are looked at closely
+        assert!(subsampled * 5 < o.width() as usize * o.height() as usize, "{}", subsampled);
+    }
+
+    #[test]
+    fn test_coverage() {
+        check_coverage(OrthoProj::new(100, 10., 20., 0.));
+        check_coverage(OrthoProj::new_with_dimensions(150, 100, 10., 20., 0.).with_radius(60.).with_offset(-45., 15.));
+        check_coverage(OrthoProj::new_with_projection(100, 100, Orthographic::new(45., 0.).with_ellipsoid(Ellipsoid::WGS84), 0.));
+        check_coverage(OrthoProj::new_with_projection(100, 100, Stereographic::new(90., 0.), 0.));
+        check_coverage(OrthoProj::new_with_projection(150, 80, PlateCarree::new(0.), 0.).with_radius(22.5));
+        check_coverage(OrthoProj::new_with_projection(150, 150, PlateCarree::new(30.), 0.).with_radius(20.).with_rotation(30.));
+        check_coverage(OrthoProj::new_with_projection(200, 100, Mollweide::new(0.), 0.).with_radius(30.));
+        check_coverage(OrthoProj::new_with_projection(150, 150, Robinson::new(-100.), 0.).with_rotation(-70.));
+    }
+
+    #[test]
+    fn test_edge_radius() {
+        let edge = [(-3., 1.), (0., 2.), (3., 3.)];
+        assert_eq!(edge_radius(&edge, 0.), 2.);
+        assert_eq!(edge_radius(&edge, 1.5), 2.5);
+        assert_eq!(edge_radius(&edge, -3.), 1.);
+        // Past the ends, in between 3 & 2π - 3
+        assert!((edge_radius(&edge, PI) - 2.).abs() < 0.2);
+    }
+}
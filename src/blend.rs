@@ -0,0 +1,234 @@
+//! Mixing new values into pixels, rather than replacing them
+
+use {Float, OrthoProj, Projection};
+
+/// How to mix a new value into a pixel, with `OrthoProj::blend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The new value on top. With an alpha channel, the old value shows through the transparent
+    /// parts, otherwise it's replaced.
+    Over,
+    /// Add the new value to the old one
+    Add,
+    /// Multiply the old value by the new one, which darkens colours
+    Multiply,
+    /// Whichever is bigger
+    Max,
+    /// Whichever is smaller
+    Min,
+}
+
+/// Pixel values which can be mixed together with a `BlendMode`.
+///
+/// This is implemented for numbers, `[T; 3]` (e.g. RGB) one channel at a time, and `[u8; 2]` &
+/// `[u8; 4]`, where the last channel is alpha. Integers are treated as going from 0 to 1 (their
+/// biggest value), like colour channels, so `Add` stops at the biggest value, and `Multiply`
+/// of 255 & 255 is 255 for `u8`.
+///
+/// Implement it for your own pixel types to use `OrthoProj::blend`.
+///
+///```
+///use orthoproj::{Blend, BlendMode};
+///assert_eq!(200u8.blend(&100, BlendMode::Add), 255);
+///assert_eq!([255u8, 0, 0, 255].blend(&[0, 0, 255, 128], BlendMode::Over), [127, 0, 128, 255]);
+///```
+pub trait Blend: Clone {
+    /// This value, with `value` mixed into it with `mode`
+    fn blend(&self, value: &Self, mode: BlendMode) -> Self;
+}
+
+macro_rules! impl_blend_int {
+    ($($t:ty),*) => {$(
+        impl Blend for $t {
+            fn blend(&self, value: &Self, mode: BlendMode) -> Self {
+                match mode {
+                    BlendMode::Over => *value,
+                    BlendMode::Add => self.saturating_add(*value),
+                    BlendMode::Multiply => {
+                        let max = <$t>::MAX as u128;
+                        ((*self as u128 * *value as u128 + max / 2) / max) as $t
+                    },
+                    BlendMode::Max => *self.max(value),
+                    BlendMode::Min => *self.min(value),
+                }
+            }
+        }
+    )*}
+}
+
+impl_blend_int!(u8, u16, u32, u64);
+
+macro_rules! impl_blend_float {
+    ($($t:ty),*) => {$(
+        impl Blend for $t {
+            fn blend(&self, value: &Self, mode: BlendMode) -> Self {
+                match mode {
+                    BlendMode::Over => *value,
+                    BlendMode::Add => self + value,
+                    BlendMode::Multiply => self * value,
+                    BlendMode::Max => self.max(*value),
+                    BlendMode::Min => self.min(*value),
+                }
+            }
+        }
+    )*}
+}
+
+impl_blend_float!(f32, f64);
+
+impl<T: Blend + Copy> Blend for [T; 3] {
+    fn blend(&self, value: &Self, mode: BlendMode) -> Self {
+        [self[0].blend(&value[0], mode), self[1].blend(&value[1], mode), self[2].blend(&value[2], mode)]
+    }
+}
+
+impl Blend for [u8; 2] {
+    fn blend(&self, value: &Self, mode: BlendMode) -> Self {
+        let mut out = [0; 2];
+        blend_alpha(self, value, mode, &mut out);
+        out
+    }
+}
+
+impl Blend for [u8; 4] {
+    fn blend(&self, value: &Self, mode: BlendMode) -> Self {
+        let mut out = [0; 4];
+        blend_alpha(self, value, mode, &mut out);
+        out
+    }
+}
+
+/// Mix `value` into `old`, where the last channel is alpha (not premultiplied). The colour
+/// channels are mixed with `mode`, and that's put over `old` with `value`'s alpha.
+fn blend_alpha(old: &[u8], value: &[u8], mode: BlendMode, out: &mut [u8]) {
+    let n = old.len() - 1;
+    let (alpha_old, alpha) = (old[n] as f64 / 255., value[n] as f64 / 255.);
+    let alpha_out = alpha + alpha_old * (1. - alpha);
+    if alpha_out <= 0. {
+        out.iter_mut().for_each(|c| *c = 0);
+        return;
+    }
+    for i in 0..n {
+        // Where there's nothing under it, the new colour is just itself
+        let mixed = if alpha_old > 0. { old[i].blend(&value[i], mode) } else { value[i] };
+        let c = (mixed as f64 * alpha + old[i] as f64 * alpha_old * (1. - alpha)) / alpha_out;
+        out[i] = c.round().clamp(0., 255.) as u8;
+    }
+    out[n] = (alpha_out * 255.).round() as u8;
+}
+
+impl<T: Blend, P: Projection> OrthoProj<T, P> {
+    /// Mix `value` into the value at `lat`, `lon` with `mode`, rather than replacing it like
+    /// `set`. Nothing happens if it's not on the globe.
+    ///
+    ///```
+    ///use orthoproj::{BlendMode, OrthoProj};
+    ///let mut image = OrthoProj::new(100, 0., 0., 0.5f32);
+    ///image.blend(10., 10., 0.25, BlendMode::Add);
+    ///image.blend(10., 10., 0.5, BlendMode::Multiply);
+    ///assert_eq!(image.get(10., 10.), Some(&0.375));
+    ///```
+    pub fn blend<F: Float>(&mut self, lat: F, lon: F, value: T, mode: BlendMode) {
+        if let Some(v) = self.get_mut(lat, lon) {
+            *v = v.blend(&value, mode);
+        }
+    }
+
+    /// Add `value` to the pixel at each `(lat, lon)` of `points`, e.g. to count how many points
+    /// are in each pixel, for a heat map. Points which aren't on the globe are skipped.
+    ///
+    ///```
+    ///use orthoproj::OrthoProj;
+    ///let mut counts = OrthoProj::new(100, 0., 0., 0u32);
+    ///counts.accumulate(vec![(10., 10.), (10., 10.), (-20., 5.), (0., 180.)], 1);
+    ///assert_eq!(counts.get(10., 10.), Some(&2));
+    ///assert_eq!(counts.iter().sum::<u32>(), 3);
+    ///```
+    pub fn accumulate<I, F>(&mut self, points: I, value: T)
+        where I: IntoIterator<Item=(F, F)>, F: Float
+    {
+        for (lat, lon) in points {
+            self.blend(lat, lon, value.clone(), BlendMode::Add);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend_numbers() {
+        assert_eq!(10u8.blend(&20, BlendMode::Over), 20);
+        assert_eq!(10u8.blend(&20, BlendMode::Add), 30);
+        assert_eq!(250u8.blend(&20, BlendMode::Add), 255);
+        assert_eq!(255u8.blend(&255, BlendMode::Multiply), 255);
+        assert_eq!(255u8.blend(&128, BlendMode::Multiply), 128);
+        assert_eq!(0u8.blend(&128, BlendMode::Multiply), 0);
+        assert_eq!(10u8.blend(&20, BlendMode::Max), 20);
+        assert_eq!(10u8.blend(&20, BlendMode::Min), 10);
+        assert_eq!(u32::MAX.blend(&1, BlendMode::Add), u32::MAX);
+        assert_eq!(65535u16.blend(&32768, BlendMode::Multiply), 32768);
+
+        assert_eq!(0.5f64.blend(&0.25, BlendMode::Add), 0.75);
+        assert_eq!(0.5f64.blend(&0.25, BlendMode::Multiply), 0.125);
+        assert_eq!(0.5f32.blend(&0.25, BlendMode::Max), 0.5);
+        assert_eq!((-1f32).blend(&0.25, BlendMode::Min), -1.);
+
+        assert_eq!([10u8, 20, 30].blend(&[1, 200, 3], BlendMode::Max), [10, 200, 30]);
+        assert_eq!([0.5f32, 1., 0.].blend(&[0.5, 0.5, 0.5], BlendMode::Multiply), [0.25, 0.5, 0.]);
+    }
+
+    #[test]
+    fn test_blend_alpha() {
+        let red = [255u8, 0, 0, 255];
+        // Opaque is the same as replacing, transparent is nothing
+        assert_eq!(red.blend(&[0, 0, 255, 255], BlendMode::Over), [0, 0, 255, 255]);
+        assert_eq!(red.blend(&[0, 0, 255, 0], BlendMode::Over), red);
+        // Half way
+        assert_eq!(red.blend(&[0, 0, 255, 128], BlendMode::Over), [127, 0, 128, 255]);
+        // On nothing, it's just the new value
+        assert_eq!([0u8, 0, 0, 0].blend(&[0, 0, 255, 128], BlendMode::Over), [0, 0, 255, 128]);
+        assert_eq!([0u8, 0, 0, 0].blend(&[0, 0, 255, 128], BlendMode::Multiply), [0, 0, 255, 128]);
+        assert_eq!([0u8, 0, 0, 0].blend(&[0, 0, 0, 0], BlendMode::Over), [0, 0, 0, 0]);
+        // Half transparent on half transparent
+        assert_eq!([100u8, 128].blend(&[200, 128], BlendMode::Over), [167, 192]);
+
+        // Other modes mix the colours, with the new alpha
+        assert_eq!(red.blend(&[0, 255, 0, 255], BlendMode::Add), [255, 255, 0, 255]);
+        assert_eq!([200u8, 200, 200, 255].blend(&[128, 255, 0, 255], BlendMode::Multiply), [100, 200, 0, 255]);
+        assert_eq!([200u8, 200, 200, 255].blend(&[128, 255, 0, 128], BlendMode::Min), [164, 200, 100, 255]);
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut o = OrthoProj::new(100, 0., 0., [0u8, 0, 0]);
+        o.blend(10., 10., [100, 0, 0], BlendMode::Add);
+        o.blend(10., 10., [100, 50, 0], BlendMode::Add);
+        assert_eq!(o.get(10., 10.), Some(&[200, 50, 0]));
+        o.blend(10., 10., [100, 100, 100], BlendMode::Max);
+        assert_eq!(o.get(10., 10.), Some(&[200, 100, 100]));
+        o.blend(10., 10., [0, 0, 0], BlendMode::Over);
+        assert_eq!(o.get(10., 10.), Some(&[0, 0, 0]));
+        // Off the globe does nothing
+        o.blend(10., 190., [1, 1, 1], BlendMode::Add);
+        assert!(o.iter().all(|&v| v == [0, 0, 0]));
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let mut o = OrthoProj::new(100, 0., 0., 0u32);
+        // A lot of points in a few places
+        let points: Vec<(f64, f64)> = (0..1000).map(|i| ((i % 4) as f64 * 10., (i % 5) as f64 * 10.)).collect();
+        o.accumulate(points.iter().cloned(), 1);
+        assert_eq!(o.iter().sum::<u32>(), 1000);
+        assert_eq!(o.iter().filter(|&&v| v > 0).count(), 20);
+        assert_eq!(o.get(0., 0.), Some(&50));
+        assert_eq!(o.get(30., 40.), Some(&50));
+
+        // Weights, rather than counts
+        let mut o = OrthoProj::new(100, 0., 0., 0f64);
+        o.accumulate(vec![(0f32, 0f32), (0., 0.)], 0.25);
+        assert_eq!(o.get(0., 0.), Some(&0.5));
+    }
+}
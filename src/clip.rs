@@ -0,0 +1,272 @@
+//! Splitting lines & polygons on the globe into the parts which can be seen, as paths of
+//! continuous (not whole pixel) points on the image. This is shared by the raster drawing and the
+//! SVG output.
+
+use std::f64::consts::PI;
+use std::mem;
+
+use {Boundary, Projection, View};
+use sphere::{self, Vec3};
+
+/// Continuous points on the image, which are joined by straight lines.
+pub type Path = Vec<(f64, f64)>;
+
+/// The visible parts of the line through `points`, with great circles between each point, as
+/// paths on the image. Where the line goes around the back, it's split, and the ends are on the
+/// edge of the globe.
+///
+/// When 2 points in a row are opposite each other, there is no one great circle between them, so
+/// the line is split there too.
+pub fn linestring<P: Projection>(view: &View<P>, points: &[Vec3]) -> Vec<Path> {
+    let visible = |p: Vec3| view.visible(p);
+
+    let mut paths = Vec::new();
+    for run in densify(view, points, false) {
+        let mut path = Vec::new();
+        if visible(run[0]) {
+            path.push(view.xy_for_vec(run[0]));
+        }
+        for pair in run.windows(2) {
+            let (prev, p) = (pair[0], pair[1]);
+            match (visible(prev), visible(p)) {
+                (true, true) => {
+                    path.push(view.xy_for_vec(p));
+                },
+                (true, false) => {
+                    // Going around the back, so stop at the edge
+                    path.push(view.xy_for_vec(horizon(view, prev, p)));
+                    paths.push(mem::take(&mut path));
+                },
+                (false, true) => {
+                    // Coming back from around the back, so start at the edge
+                    path.push(view.xy_for_vec(horizon(view, p, prev)));
+                    path.push(view.xy_for_vec(p));
+                },
+                (false, false) => {},
+            }
+        }
+        if !path.is_empty() {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The visible part of the polygon `ring`, with great circles between each point, as closed
+/// paths on the image (the last point isn't repeated). The inside of the polygon is the inside of
+/// an odd number of these paths.
+///
+/// The inside is the smaller of the 2 parts of the globe which the ring splits it into. Where
+/// the ring goes around the back, it's closed along the edge of the globe.
+pub fn ring<P: Projection>(view: &View<P>, ring: &[Vec3]) -> Vec<Path> {
+    let mut ring = ring.to_vec();
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    if ring.len() < 3 {
+        return Vec::new();
+    }
+
+    let visible = |p: Vec3| view.visible(p);
+
+    let mut points: Vec<Vec3> = densify(view, &ring, true).into_iter().flatten().collect();
+    points.pop();
+
+    let inside_left = left_area(&ring) <= 2. * PI;
+    // Which side of the ring the centre of the map (and the point opposite it) are on
+    let centre_inside = |points: &[Vec3]| {
+        let w = winding(view, points);
+        if w == 0 {
+            None
+        } else {
+            Some((w > 0) == inside_left)
+        }
+    };
+
+    let first_hidden = match points.iter().position(|&p| !visible(p)) {
+        None => {
+            // All on the map, so it's just a normal polygon. When the map is more than a
+            // hemisphere, the polygon can go around all of the part which isn't on the map, and if
+            // it does, it's everything between the ring & the edge.
+            let mut paths = vec![points.iter().map(|&p| view.xy_for_vec(p)).collect()];
+            if centre_inside(&points) == Some(false) {
+                paths.push(edge(view));
+            }
+            return paths;
+        },
+        Some(i) => i,
+    };
+    // Start off the map, so every visible part is a whole run of points
+    points.rotate_left(first_hidden);
+    points.push(points[0]);
+
+    // Each visible part of the ring, from where it comes over the edge to where it goes back, and
+    // where on the edge it starts & ends
+    let mut parts: Vec<(Path, Vec3, Vec3)> = Vec::new();
+    let mut part = Vec::new();
+    let mut part_start = points[0];
+    for pair in points.windows(2) {
+        let (prev, p) = (pair[0], pair[1]);
+        match (visible(prev), visible(p)) {
+            (false, true) => {
+                part_start = horizon(view, p, prev);
+                part.push(view.xy_for_vec(part_start));
+                part.push(view.xy_for_vec(p));
+            },
+            (true, true) => {
+                part.push(view.xy_for_vec(p));
+            },
+            (true, false) => {
+                let end = horizon(view, prev, p);
+                part.push(view.xy_for_vec(end));
+                parts.push((mem::take(&mut part), part_start, end));
+            },
+            (false, false) => {},
+        }
+    }
+
+    if parts.is_empty() {
+        // All off the map. When the map is less than a hemisphere, the polygon can go around all
+        // of it, and if it does, it goes around the centre of the map.
+        if centre_inside(&points) == Some(true) {
+            return vec![edge(view)];
+        }
+        return Vec::new();
+    }
+
+    // Going along the edge of the map from where the ring goes off it, the inside of the polygon
+    // is on the same side as along the ring. So we go anticlockwise (looking from the front) if
+    // the inside is on the left, to the next place the ring comes back.
+    let turn_to = |from: f64, to: f64| {
+        let turn = if inside_left { to - from } else { from - to };
+        turn.rem_euclid(2. * PI)
+    };
+
+    let mut paths = Vec::new();
+    let mut done = vec![false; parts.len()];
+    while let Some(first) = done.iter().position(|&d| !d) {
+        let mut path = Vec::new();
+        let mut i = first;
+        loop {
+            done[i] = true;
+            path.extend_from_slice(&parts[i].0);
+
+            let start_angle = view.angle_of(parts[i].2);
+            let (next, turn) = (0..parts.len())
+                .map(|j| (j, turn_to(start_angle, view.angle_of(parts[j].1))))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .unwrap();
+
+            let steps = steps_for_length(view.edge_length(turn));
+            let direction = if inside_left { 1. } else { -1. };
+            for j in 1..steps {
+                let a = start_angle + direction * turn * j as f64 / steps as f64;
+                path.push(view.xy_for_vec(view.edge_point(a)));
+            }
+
+            if done[next] {
+                break;
+            }
+            i = next;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+/// How many times the closed ring `points` goes anticlockwise around the centre of the map
+/// (looking from the front). When it's not 0, the centre is on one side of the ring, & the point
+/// opposite is on the other.
+fn winding<P: Projection>(view: &View<P>, points: &[Vec3]) -> i32 {
+    if let Boundary::Antimeridian(_) = view.boundary {
+        // The part off a world map goes from pole to pole, so a ring can't go around it, or the
+        // map, without crossing it
+        return 0;
+    }
+    let turns: f64 = points.iter().zip(points.iter().cycle().skip(1))
+        .map(|(&a, &b)| (view.angle_of(b) - view.angle_of(a) + PI).rem_euclid(2. * PI) - PI)
+        .sum();
+    (turns / (2. * PI)).round() as i32
+}
+
+/// The whole edge of the map, as a closed path
+fn edge<P: Projection>(view: &View<P>) -> Path {
+    let steps = steps_for_length(view.edge_length(2. * PI));
+    (0..steps)
+        .map(|i| view.xy_for_vec(view.edge_point(2. * PI * i as f64 / steps as f64)))
+        .collect()
+}
+
+/// Split up `points` into runs of points at most half a pixel apart along great circles. Where 2
+/// points next to each other are opposite each other, a new run is started. If `closed`, the last
+/// point is joined back to the first. On world maps, a point on the cut is added wherever the line
+/// crosses it.
+fn densify<P: Projection>(view: &View<P>, points: &[Vec3], closed: bool) -> Vec<Vec<Vec3>> {
+    let mut runs = Vec::new();
+    if points.is_empty() {
+        return runs;
+    }
+
+    let mut run = vec![points[0]];
+    let n = if closed { points.len() } else { points.len() - 1 };
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i+1) % points.len()]);
+        let angle = sphere::angle(a, b);
+        if angle > PI - 1e-9 {
+            runs.push(mem::replace(&mut run, vec![b]));
+            continue;
+        }
+        let steps = steps_for_angle(view, angle);
+        for j in 1..=steps {
+            let p = if j == steps { b } else { sphere::slerp(a, b, angle, j as f64 / steps as f64) };
+            // Make sure there's a point off the map wherever the line is cut on a world map
+            if let Some(seam) = view.seam_crossing(run[run.len()-1], p) {
+                run.push(seam);
+            }
+            run.push(p);
+        }
+    }
+    runs.push(run);
+    runs
+}
+
+/// How many steps to split up a great circle of `angle` radians into, so that each one is at most
+/// half a pixel on the image.
+fn steps_for_angle<P: Projection>(view: &View<P>, angle: f64) -> u32 {
+    steps_for_length(angle * view.radius * view.max_scale)
+}
+
+/// How many steps to split up a line `length` pixels long into, so that each one is at most half a
+/// pixel.
+fn steps_for_length(length: f64) -> u32 {
+    (length * 2.).ceil().max(1.) as u32
+}
+
+/// The area, in steradians, of the part of the globe to the left of the ring, going along it,
+/// looking from outside the globe.
+fn left_area(ring: &[Vec3]) -> f64 {
+    // Add up the signed areas of the triangles from the first point
+    let a = ring[0];
+    let mut area = 0.;
+    for pair in ring[1..].windows(2) {
+        let (b, c) = (pair[0], pair[1]);
+        let det = sphere::dot(a, sphere::cross(b, c));
+        area += 2. * det.atan2(1. + sphere::dot(a, b) + sphere::dot(b, c) + sphere::dot(c, a));
+    }
+    area.rem_euclid(4. * PI)
+}
+
+/// The point where the great circle from `front` (on the map) to `back` (off it) goes over the
+/// edge of the map, staying just on the map. `front` and `back` should be close together.
+fn horizon<P: Projection>(view: &View<P>, front: Vec3, back: Vec3) -> Vec3 {
+    let (mut front, mut back) = (front, back);
+    for _ in 0..40 {
+        let mid = sphere::midpoint(front, back);
+        if view.visible(mid) {
+            front = mid;
+        } else {
+            back = mid;
+        }
+    }
+    front
+}
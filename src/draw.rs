@@ -0,0 +1,493 @@
+//! Drawing lines and polygons on an `OrthoProj`
+
+use {pixel_floor, Float, OrthoProj, Projection};
+use clip::{self, Path};
+use sphere;
+
+impl<T: Clone, P: Projection> OrthoProj<T, P> {
+    /// Draw the shortest line along the surface of the globe (a great circle) from `from` to `to`,
+    /// both `(lat, lon)`, setting those pixels to `value`.
+    ///
+    /// The line has no gaps between pixels. If it goes around the back of the globe, it stops at
+    /// the edge of the globe, and starts again where it comes back.
+    ///
+    /// When `from` & `to` are on opposite sides of the globe, there is no one shortest line, so
+    /// only those 2 points are drawn.
+    pub fn draw_great_circle<F: Float>(&mut self, from: (F, F), to: (F, F), value: T) {
+        self.draw_linestring(&[from, to], value);
+    }
+
+    /// Draw a line through all the `(lat, lon)` `points`, with great circles between each
+    /// point, setting those pixels to `value`. The line has no gaps between pixels, and stops at
+    /// the edge of the globe, like `draw_great_circle`.
+    pub fn draw_linestring<F: Float>(&mut self, points: &[(F, F)], value: T) {
+        let points: Vec<_> = points.iter().map(|&(lat, lon)| sphere::from_pos(lat.to_f64(), lon.to_f64())).collect();
+        for path in clip::linestring(&self._view, &points) {
+            self.draw_path(&path, &value);
+        }
+    }
+
+    /// Set every pixel inside the polygon with this ring of `(lat, lon)` points to `value`. The
+    /// edges are great circles. The ring doesn't need to repeat the first point at the end.
+    ///
+    /// The inside is the smaller of the 2 parts of the globe the ring splits it into, so it
+    /// doesn't matter whether the points go clockwise or anticlockwise. Parts of the polygon
+    /// around the back of the globe aren't drawn, and where the polygon goes over the edge of the
+    /// globe, it's filled up to the edge.
+    pub fn fill_polygon<F: Float>(&mut self, ring: &[(F, F)], value: T) {
+        self.fill_polygon_with_holes::<F, &[(F, F)]>(ring, &[], value);
+    }
+
+    /// Like `fill_polygon`, but pixels inside any of the `holes` rings are not changed.
+    pub fn fill_polygon_with_holes<F, R>(&mut self, exterior: &[(F, F)], holes: &[R], value: T)
+        where F: Float, R: AsRef<[(F, F)]>
+    {
+        let mut paths = Vec::new();
+        for ring in Some(exterior).into_iter().chain(holes.iter().map(|h| h.as_ref())) {
+            let ring: Vec<_> = ring.iter().map(|&(lat, lon)| sphere::from_pos(lat.to_f64(), lon.to_f64())).collect();
+            paths.extend(clip::ring(&self._view, &ring));
+        }
+        self.fill_paths(&paths, &value);
+    }
+
+    /// Set every pixel whose centre is inside an odd number of the closed `paths` to `value`.
+    fn fill_paths(&mut self, paths: &[Path], value: &T) {
+        // Every edge which isn't flat, with its top first, and the rows it might cross the centre
+        // of (give or take one, which is checked exactly below), sorted by the first row
+        let mut edges = Vec::new();
+        for path in paths {
+            for (i, &a) in path.iter().enumerate() {
+                let b = path[(i+1) % path.len()];
+                if a.1 == b.1 {
+                    continue;
+                }
+                let (top, bottom) = if a.1 < b.1 { (a, b) } else { (b, a) };
+                let rows = ((top.1 - 0.5).floor() as i64, (bottom.1 - 0.5).ceil() as i64);
+                edges.push((rows, top, bottom));
+            }
+        }
+        edges.sort_by_key(|&((first, _), _, _)| first);
+
+        let mut active = Vec::new();
+        let mut next = 0;
+        let mut crossings = Vec::new();
+        for y in 0..self._height {
+            let yc = y as f64 + 0.5;
+            while next < edges.len() && (edges[next].0).0 <= y as i64 {
+                active.push(edges[next]);
+                next += 1;
+            }
+            active.retain(|&((_, last), _, _)| last >= y as i64);
+
+            crossings.clear();
+            for &(_, (x0, y0), (x1, y1)) in &active {
+                if y0 <= yc && yc < y1 {
+                    crossings.push(x0 + (yc - y0) * (x1 - x0) / (y1 - y0));
+                }
+            }
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            for pair in crossings.chunks(2) {
+                if pair.len() < 2 {
+                    break;
+                }
+                // Pixels whose centre is in [pair[0], pair[1])
+                let start = (pair[0] - 0.5).ceil().max(0.);
+                let end = (pair[1] - 0.5).ceil().min(self._width as f64);
+                if start >= end {
+                    continue;
+                }
+                for x in start as u32..end as u32 {
+                    self.set_pixel(x, y, value.clone());
+                }
+            }
+        }
+    }
+
+    /// Set all the pixels along `path` to `value`, with no gaps.
+    fn draw_path(&mut self, path: &Path, value: &T) {
+        let pixel = |(x, y): (f64, f64)| (pixel_floor(x) as i64, pixel_floor(y) as i64);
+        let mut last = pixel(path[0]);
+        self.plot(last.0, last.1, value);
+        for &xy in &path[1..] {
+            let next = pixel(xy);
+            if next != last {
+                self.draw_pixel_line(last, next, value);
+                last = next;
+            }
+        }
+    }
+
+    /// Set all the pixels on the straight line from `from` to `to`, using Bresenham's algorithm.
+    fn draw_pixel_line(&mut self, from: (i64, i64), to: (i64, i64), value: &T) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.plot(x, y, value);
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Set pixel (`x`, `y`) to `value`, if it's on the image.
+    fn plot(&mut self, x: i64, y: i64, value: &T) {
+        if x >= 0 && y >= 0 && x < self._width as i64 && y < self._height as i64 {
+            self.set_pixel(x as u32, y as u32, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Gnomonic, LambertAzimuthalEqualArea, Mollweide, OrthoProj, PlateCarree, Projection, Stereographic, WebMercator};
+    use test_helpers::count;
+
+    /// How many 8-connected groups of pixels are set to `value`
+    fn groups<P: Projection>(o: &OrthoProj<u8, P>, value: u8) -> usize {
+        let (w, h) = (o.width() as i64, o.height() as i64);
+        let mut seen = vec![false; (w*h) as usize];
+        let mut groups = 0;
+        for (x, y, &v) in o.enumerate_pixels() {
+            let i = (y as i64*w + x as i64) as usize;
+            if v != value || seen[i] {
+                continue;
+            }
+            groups += 1;
+            seen[i] = true;
+            let mut todo = vec![(x as i64, y as i64)];
+            while let Some((x, y)) = todo.pop() {
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        let (nx, ny) = (x+dx, y+dy);
+                        if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                            continue;
+                        }
+                        let ni = (ny*w + nx) as usize;
+                        if !seen[ni] && *o.get_pixel(nx as u32, ny as u32) == value {
+                            seen[ni] = true;
+                            todo.push((nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+        groups
+    }
+
+    #[test]
+    fn test_draw_great_circle() {
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_great_circle((0., -30.), (0., 30.), 1);
+        let (x0, y0) = o.xy_for_pos(0., -30.).unwrap();
+        let (x1, y1) = o.xy_for_pos(0., 30.).unwrap();
+        assert_eq!(y0, y1);
+        for x in 0..200 {
+            assert_eq!(o.get_pixel(x, y0), if x >= x0 && x <= x1 { &1 } else { &0 }, "x = {}", x);
+        }
+        assert_eq!(o.iter().filter(|&&v| v == 1).count() as u32, x1 - x0 + 1);
+    }
+
+    #[test]
+    fn test_draw_great_circle_horizon() {
+        // Goes off the east edge
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_great_circle((0., 0.), (0., 150.), 1);
+        assert_eq!(o.get_pixel(100, 100), &1);
+        assert_eq!(o.get_pixel(199, 100), &1);
+        assert_eq!(o.iter().filter(|&&v| v == 1).count(), 100);
+
+        // Comes from behind the west edge
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_great_circle((30., -150.), (0., 0.), 1);
+        assert_eq!(o.get_pixel(100, 100), &1);
+        assert_eq!(groups(&o, 1), 1);
+        // It starts at the edge of the globe
+        let (x, y) = o.enumerate_pixels().filter(|p| *p.2 == 1).map(|(x, y, _)| (x, y)).min().unwrap();
+        let (dx, dy) = (x as f32 - 100., y as f32 - 100.);
+        assert!((dx*dx + dy*dy).sqrt() > 98.);
+
+        // All behind
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_great_circle((10., 100.), (-10., -100.), 1);
+        assert!(o.iter().all(|&v| v == 0));
+
+        // Opposite
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_great_circle((10., 10.), (-10., -170.), 1);
+        assert_eq!(o.iter().filter(|&&v| v == 1).count(), 1);
+        assert_eq!(o.get(10., 10.), Some(&1));
+    }
+
+    #[test]
+    fn test_draw_linestring() {
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_linestring(&[(0., -30.), (0., 30.), (30., 30.), (30., 120.)], 1);
+        assert_eq!(groups(&o, 1), 1);
+        for &(lat, lon) in &[(0., -30.), (0., 0.), (0., 30.), (15., 30.), (30., 30.)] {
+            assert_eq!(o.get(lat, lon), Some(&1), "{:?}", (lat, lon));
+        }
+
+        // Goes around the back and comes back
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_linestring(&[(0., 30.), (0., 150.), (0., -150.), (0., -30.)], 1);
+        assert_eq!(groups(&o, 1), 2);
+
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_linestring(&[(10., 10.)], 1);
+        assert_eq!(count(&o, 1), 1);
+        o.draw_linestring::<f32>(&[], 1);
+        assert_eq!(count(&o, 1), 1);
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        let square = [(-20., -20.), (-20., 20.), (20., 20.), (20., -20.)];
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.fill_polygon(&square, 1);
+        assert_eq!(groups(&o, 1), 1);
+        assert_eq!(o.get(0., 0.), Some(&1));
+        assert_eq!(o.get(19., 19.), Some(&1));
+        assert_eq!(o.get(-19., 0.), Some(&1));
+        assert_eq!(o.get(0., 21.), Some(&0));
+        assert_eq!(o.get(25., 0.), Some(&0));
+
+        // Same the other way around, and when closed
+        let mut reversed: Vec<_> = square.iter().rev().cloned().collect();
+        reversed.push(reversed[0]);
+        let mut o2 = OrthoProj::new(200, 0., 0., 0u8);
+        o2.fill_polygon(&reversed, 1);
+        assert_eq!(o.as_slice(), o2.as_slice());
+
+        // With a hole
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.fill_polygon_with_holes(&square, &[vec![(-5., -5.), (-5., 5.), (5., 5.), (5., -5.)]], 1);
+        assert_eq!(groups(&o, 1), 1);
+        assert_eq!(o.get(0., 0.), Some(&0));
+        assert_eq!(o.get(10., 0.), Some(&1));
+        assert_eq!(o.get(0., -10.), Some(&1));
+
+        // All behind
+        let mut o = OrthoProj::new(200, 0., 180., 0u8);
+        o.fill_polygon(&square, 1);
+        assert_eq!(count(&o, 1), 0);
+    }
+
+    #[test]
+    fn test_fill_polygon_over_edge() {
+        // Half is around the back, so it's filled up to the edge
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.fill_polygon(&[(-30., 60.), (-30., 120.), (30., 120.), (30., 60.)], 1);
+        assert_eq!(groups(&o, 1), 1);
+        assert_eq!(o.get(0., 70.), Some(&1));
+        assert_eq!(o.get(0., 89.), Some(&1));
+        assert_eq!(o.get(29., 80.), Some(&1));
+        assert_eq!(o.get(0., 50.), Some(&0));
+        assert_eq!(o.get(40., 80.), Some(&0));
+        assert_eq!(o.get_pixel(199, 100), &1);
+        assert_eq!(o.get_pixel(199, 50), &0);
+
+        // Around the south pole, which is behind
+        let ring: Vec<_> = (0..36).map(|i| (-60., (i*10) as f32)).collect();
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.fill_polygon(&ring, 1);
+        assert_eq!(groups(&o, 1), 1);
+        assert_eq!(o.get(-65., 0.), Some(&1));
+        assert_eq!(o.get(-61., 60.), Some(&1));
+        assert_eq!(o.get(-55., 0.), Some(&0));
+        assert_eq!(o.get(0., 0.), Some(&0));
+        assert_eq!(o.get_pixel(100, 199), &1);
+
+        // Zig zag around the north, going over the edge a few times
+        let ring: Vec<_> = (0..8).map(|i| {
+            let lon = (i*45) as f32;
+            if i % 2 == 0 { (20., lon) } else { (40., lon) }
+        }).collect();
+        let mut o = OrthoProj::new(200, -20., 0., 0u8);
+        o.fill_polygon(&ring, 1);
+        assert_eq!(o.get(60., 0.), Some(&1));
+        assert_eq!(o.get(45., 60.), Some(&1));
+        assert_eq!(o.get(10., 0.), Some(&0));
+        assert_eq!(o.get(50., 80.), None);
+        assert_eq!(o.get_pixel(100, 2), &1);
+        assert_eq!(o.get_pixel(100, 195), &0);
+    }
+
+    #[test]
+    fn test_draw_projections() {
+        // Great circles are straight lines on the gnomonic projection
+        let mut o = OrthoProj::new_with_projection(200, 200, Gnomonic::new(10., 0.), 0u8);
+        o.draw_great_circle((0., -30.), (30., 30.), 1);
+        let (x1, y1) = o.xy_for_pos(0., -30.).unwrap();
+        let (x2, y2) = o.xy_for_pos(30., 30.).unwrap();
+        let (dx, dy) = (x2 as f64 - x1 as f64, y2 as f64 - y1 as f64);
+        for (x, y, &v) in o.enumerate_pixels() {
+            if v == 1 {
+                let cross = (x as f64 - x1 as f64) * dy - (y as f64 - y1 as f64) * dx;
+                assert!(cross.abs() / dx.hypot(dy) < 1.5, "({}, {})", x, y);
+            }
+        }
+
+        // Around the edge of a stereographic map
+        let mut o = OrthoProj::new_with_projection(200, 200, Stereographic::new(0., 0.).with_max_angle(120.), 0u8);
+        o.draw_linestring(&[(0., 100.), (0., 110.), (0., 130.), (10., 130.)], 1);
+        assert_eq!(groups(&o, 1), 1);
+        assert!(o.get(0., 110.).is_some());
+
+        // A polygon around the whole gnomonic map fills it
+        let mut o = OrthoProj::new_with_projection(200, 200, Gnomonic::new(90., 0.), 0u8);
+        let ring: Vec<(f32, f32)> = (0..36).map(|i| (15., i as f32 * 10. - 180.)).collect();
+        o.fill_polygon(&ring, 1);
+        let mut all = OrthoProj::new_with_projection(200, 200, Gnomonic::new(90., 0.), 0u8);
+        all.fill_globe(1);
+        assert!((count(&o, 1) as i64 - count(&all, 1) as i64).abs() < 100, "{} {}", count(&o, 1), count(&all, 1));
+        // Either way around, as the inside is the smaller side
+        let mut o = OrthoProj::new_with_projection(200, 200, Gnomonic::new(90., 0.), 0u8);
+        let ring: Vec<(f32, f32)> = ring.into_iter().rev().collect();
+        o.fill_polygon(&ring, 1);
+        assert!((count(&o, 1) as i64 - count(&all, 1) as i64).abs() < 100);
+        // & not when the map is on the other side
+        let mut o = OrthoProj::new_with_projection(200, 200, Gnomonic::new(-90., 0.), 0u8);
+        o.fill_polygon(&ring, 1);
+        assert_eq!(count(&o, 1), 0);
+
+        // Going around the back of a whole world map
+        let mut o = OrthoProj::new_with_projection(200, 200, LambertAzimuthalEqualArea::new(0., 0.), 0u8);
+        o.fill_polygon(&[(-60., 120.), (-60., -120.), (60., -120.), (60., 120.)], 1);
+        assert_eq!(o.get(0., 0.), Some(&0));
+        assert_eq!(o.get(0., 90.), Some(&0));
+        assert_eq!(o.get(0., 175.), Some(&1));
+        assert_eq!(o.get(0., -175.), Some(&1));
+    }
+
+    #[test]
+    fn test_draw_world() {
+        // Lines are cut where they cross the meridian opposite the centre
+        let mut o = OrthoProj::new_with_projection(360, 180, PlateCarree::new(0.), 0u8);
+        o.draw_linestring(&[(10.5, 170.), (10.5, -170.)], 1);
+        assert_eq!(groups(&o, 1), 2);
+        assert!(count(&o, 1) < 30, "{}", count(&o, 1));
+        assert_eq!(o.get(10.5, 175.), Some(&1));
+        assert_eq!(o.get(10.5, -175.), Some(&1));
+        assert_eq!(o.get(10.5, 0.), Some(&0));
+
+        // Polygons too, & the inside is still the smaller side
+        let mut o = OrthoProj::new_with_projection(360, 180, PlateCarree::new(0.), 0u8);
+        o.fill_polygon(&[(-10., 170.), (-10., -170.), (10., -170.), (10., 170.)], 1);
+        assert_eq!(o.get(0., 175.), Some(&1));
+        assert_eq!(o.get(0., -175.), Some(&1));
+        assert_eq!(o.get(0., 0.), Some(&0));
+        assert!((count(&o, 1) as i64 - 20*20).abs() < 40, "{}", count(&o, 1));
+
+        // A polygon around the south pole, like Antarctica, goes down to the bottom edge
+        let mut o = OrthoProj::new_with_projection(400, 200, Mollweide::new(0.), 0u8);
+        let ring: Vec<(f32, f32)> = (0..36).map(|i| (-70., i as f32 * 10. - 180.)).collect();
+        o.fill_polygon(&ring, 1);
+        assert_eq!(o.get(-80., 0.), Some(&1));
+        assert_eq!(o.get(-80., 179.), Some(&1));
+        assert_eq!(o.get(-60., 0.), Some(&0));
+        assert_eq!(o.get(60., 0.), Some(&0));
+        assert_eq!(o.get_pixel(200, 199), &1);
+        assert_eq!(o.get_pixel(200, 1), &0);
+
+        // Web Mercator stops short of the poles
+        let mut o = OrthoProj::new_with_projection(200, 200, WebMercator::new(0.), 0u8);
+        o.draw_great_circle((80., -90.), (80., 90.), 1);
+        assert_eq!(groups(&o, 1), 2);
+        assert_eq!(o.get_pixel(100, 0), &0);
+        assert_eq!(o.get(80., -90.), Some(&1));
+    }
+
+    #[test]
+    fn test_draw_rotated() {
+        // Turning the globe a quarter turn turns what's drawn the same way
+        let draw = |rotation: f64| {
+            let mut o = OrthoProj::new(100, 30., 10., 0u8).with_rotation(rotation);
+            o.draw_linestring(&[(10., -20.), (60., 40.), (40., 120.)], 1);
+            // Goes over the edge, so is filled along it
+            o.fill_polygon(&[(-40., 0.), (-40., 150.), (-10., 150.), (-10., 0.)], 2);
+            o
+        };
+        let (o, turned) = (draw(0.), draw(90.));
+        let mut same = 0;
+        for (x, y, &v) in o.enumerate_pixels() {
+            if v != 0 && turned.get_pixel(y, 99 - x) == &v {
+                same += 1;
+            }
+        }
+        assert!(count(&o, 1) > 50 && count(&o, 2) > 300, "{} {}", count(&o, 1), count(&o, 2));
+        assert!(same as f64 > 0.95 * (count(&o, 1) + count(&o, 2)) as f64, "{}", same);
+        assert_eq!(groups(&turned, 1), 1);
+        assert_eq!(count(&o, 2), count(&turned, 2));
+    }
+
+    proptest! {
+        #[test]
+        fn fill_polygon_triangle(size in 10u32..300, lat0 in -90f32..90., lon0 in -180f32..180., points in proptest::collection::vec((-90f32..90., -180f32..180.), 3)) {
+            use sphere;
+            let mut o = OrthoProj::new(size, lat0, lon0, 0u8);
+            o.fill_polygon(&points, 1);
+
+            let v: Vec<_> = points.iter().map(|&(lat, lon)| sphere::from_pos(lat as f64, lon as f64)).collect();
+            let normals = [sphere::cross(v[0], v[1]), sphere::cross(v[1], v[2]), sphere::cross(v[2], v[0])];
+            prop_assume!(normals.iter().all(|&n| sphere::length(n) > 0.01));
+
+            let margin = 2. / o.radius() as f64;
+            for (x, y, pos, &value) in o.iter_geo() {
+                let pos = match pos {
+                    None => { prop_assert_eq!(value, 0); continue; },
+                    Some(pos) => sphere::from_pos(pos.0 as f64, pos.1 as f64),
+                };
+                // Distance (ish) from the great circle of each side. Skip pixels near the edges
+                let sides: Vec<f64> = normals.iter().map(|&n| sphere::dot(pos, n) / sphere::length(n)).collect();
+                if sides.iter().any(|s| s.abs() < margin) {
+                    continue;
+                }
+                // Inside is on the same side of all of them as the other point of the triangle
+                let orientation = sphere::dot(v[0], normals[1]);
+                let inside = sides.iter().all(|&s| s * orientation > 0.);
+                prop_assert_eq!(value, inside as u8, "pixel ({}, {})", x, y);
+            }
+        }
+
+
+        #[test]
+        fn great_circle_has_no_gaps(size in 2u32..500, lat0 in -90f32..90., lon0 in -180f32..180., lat1 in -90f32..90., lon1 in -180f32..180., lat2 in -90f32..90., lon2 in -180f32..180.) {
+            let mut o = OrthoProj::new(size, lat0, lon0, 0u8);
+            o.draw_great_circle((lat1, lon1), (lat2, lon2), 1);
+
+            // The visible side is convex, so if the ends are visible, all of the line is
+            if let (Some((x1, y1)), Some((x2, y2))) = (o.xy_for_pos(lat1, lon1), o.xy_for_pos(lat2, lon2)) {
+                prop_assert_eq!(o.get_pixel(x1, y1), &1);
+                prop_assert_eq!(o.get_pixel(x2, y2), &1);
+                prop_assert_eq!(groups(&o, 1), 1);
+            }
+        }
+
+        #[test]
+        fn great_circle_has_no_gaps_stereographic(size in 2u32..500, lat0 in -90f64..90., lon0 in -180f64..180., lat1 in -90f32..90., lon1 in -180f32..180., lat2 in -90f32..90., lon2 in -180f32..180.) {
+            // Stretched up to twice as much at the edge, so lines need splitting up more
+            let mut o = OrthoProj::new_with_projection(size, size, Stereographic::new(lat0, lon0), 0u8);
+            o.draw_great_circle((lat1, lon1), (lat2, lon2), 1);
+            if let (Some((x1, y1)), Some((x2, y2))) = (o.xy_for_pos(lat1, lon1), o.xy_for_pos(lat2, lon2)) {
+                prop_assert_eq!(o.get_pixel(x1, y1), &1);
+                prop_assert_eq!(o.get_pixel(x2, y2), &1);
+                prop_assert_eq!(groups(&o, 1), 1);
+            }
+        }
+    }
+}
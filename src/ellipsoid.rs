@@ -0,0 +1,112 @@
+//! The shape of the Earth, for projections which don't treat it as a sphere
+
+/// An ellipsoid of revolution: a sphere squashed a little at the poles, like the Earth.
+///
+/// Latitudes on an ellipsoid are geodetic, like GPS & most maps: the angle between the equator
+/// & the line straight up from the ground (the normal), which doesn't quite go through the centre
+/// of the Earth. The angle from the centre is the geocentric latitude, which is up to 0.19° less
+/// on WGS84. Use `geodetic_latitude` to convert those.
+///
+///```
+///use orthoproj::Ellipsoid;
+///let lat = Ellipsoid::WGS84.geocentric_latitude(45.);
+///assert!((lat - 44.8076).abs() < 1e-4);
+///```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ellipsoid {
+    semi_major: f64,
+    flattening: f64,
+}
+
+impl Ellipsoid {
+    /// The World Geodetic System 1984 ellipsoid, used by GPS
+    pub const WGS84: Ellipsoid = Ellipsoid{ semi_major: 6378137., flattening: 1. / 298.257223563 };
+
+    /// The Geodetic Reference System 1980 ellipsoid, which is almost exactly WGS84
+    pub const GRS80: Ellipsoid = Ellipsoid{ semi_major: 6378137., flattening: 1. / 298.257222101 };
+
+    /// An ellipsoid with an equatorial radius of `semi_major`, and flattened by
+    /// 1/`inverse_flattening` at the poles (like PROJ's `+a` & `+rf`). An `inverse_flattening`
+    /// of 0 or infinity is a sphere, like PROJ. Panics unless `semi_major` is more than 0, &
+    /// `inverse_flattening` is more than 1, 0 or infinity.
+    pub fn new(semi_major: f64, inverse_flattening: f64) -> Self {
+        assert!(semi_major > 0., "semi_major must be more than 0, not {}", semi_major);
+        if inverse_flattening == 0. || inverse_flattening == f64::INFINITY {
+            return Ellipsoid{ semi_major, flattening: 0. };
+        }
+        assert!(inverse_flattening > 1., "inverse_flattening must be more than 1, 0 or infinity, not {}", inverse_flattening);
+        Ellipsoid{ semi_major, flattening: 1. / inverse_flattening }
+    }
+
+    /// The radius at the equator. Projections use this as the radius of the globe, so a map
+    /// can be scaled up by this to get metres (or whatever unit it's in).
+    pub fn semi_major(&self) -> f64 {
+        self.semi_major
+    }
+
+    /// How much shorter the polar radius is than the equatorial one, as a fraction of it
+    pub fn flattening(&self) -> f64 {
+        self.flattening
+    }
+
+    /// The square of the eccentricity, e², which most of the maths uses
+    pub fn eccentricity_squared(&self) -> f64 {
+        self.flattening * (2. - self.flattening)
+    }
+
+    /// The geocentric latitude (in degrees) of the point at geodetic latitude `lat`
+    pub fn geocentric_latitude(&self, lat: f64) -> f64 {
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        ((1. - self.eccentricity_squared()) * sin_lat).atan2(cos_lat).to_degrees()
+    }
+
+    /// The geodetic latitude (in degrees) of the point at geocentric latitude `lat`
+    pub fn geodetic_latitude(&self, lat: f64) -> f64 {
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        sin_lat.atan2((1. - self.eccentricity_squared()) * cos_lat).to_degrees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ellipsoid() {
+        let wgs84 = Ellipsoid::WGS84;
+        assert_eq!(Ellipsoid::new(6378137., 298.257223563), wgs84);
+        // The polar radius
+        assert!((wgs84.semi_major() * (1. - wgs84.flattening()) - 6356752.314245).abs() < 1e-5);
+        assert!((wgs84.eccentricity_squared() - 0.00669437999014).abs() < 1e-14);
+
+        for &lat in &[-90f64, -60., -0.5, 0., 10., 45., 89.9, 90.] {
+            let geocentric = wgs84.geocentric_latitude(lat);
+            assert!((wgs84.geodetic_latitude(geocentric) - lat).abs() < 1e-12);
+            assert!(geocentric.abs() <= lat.abs());
+        }
+        assert_eq!(wgs84.geocentric_latitude(90.), 90.);
+        // Biggest difference is near 45°
+        assert!((45. - wgs84.geocentric_latitude(45.) - 0.19242).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sphere() {
+        for &inverse_flattening in &[0., f64::INFINITY] {
+            let sphere = Ellipsoid::new(2., inverse_flattening);
+            assert_eq!((sphere.semi_major(), sphere.flattening(), sphere.eccentricity_squared()), (2., 0., 0.));
+            assert_eq!(sphere.geocentric_latitude(45.), 45.);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "inverse_flattening must be more than 1")]
+    fn test_too_flattened() {
+        Ellipsoid::new(1., 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "inverse_flattening must be more than 1")]
+    fn test_negative_flattening() {
+        Ellipsoid::new(1., -300.);
+    }
+}
@@ -0,0 +1,324 @@
+//! Reprojecting equirectangular (plate carrée) images, like most world maps, onto the globe
+
+use {Float, OrthoProj};
+
+/// How to get a value from an image at a point which is between pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sampling {
+    /// Use the closest pixel
+    Nearest,
+    /// Mix the 4 closest pixels, weighted by how close they are
+    Bilinear,
+    /// Mix the 16 closest pixels, with a (Catmull-Rom) bicubic curve. This is sharper than
+    /// bilinear.
+    Bicubic,
+}
+
+/// Pixel values which can be mixed together, for `Sampling::Bilinear` and `Sampling::Bicubic`,
+/// and for smooth edges with `OrthoProj::fill_globe_antialiased`.
+///
+/// This is implemented for `f32`, `f64`, integers up to 32 bits, and arrays of them, like
+/// `[u8; 3]` for RGB.
+pub trait Interpolate: Clone {
+    /// Add up these values, each multiplied by its weight. The weights add up to 1, but some can
+    /// be negative, so integer types should be rounded and clamped.
+    fn weighted_sum(values: &[(Self, f64)]) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn weighted_sum(values: &[(Self, f64)]) -> Self {
+        values.iter().map(|&(v, w)| v * w).sum()
+    }
+}
+
+impl Interpolate for f32 {
+    fn weighted_sum(values: &[(Self, f64)]) -> Self {
+        values.iter().map(|&(v, w)| v as f64 * w).sum::<f64>() as f32
+    }
+}
+
+macro_rules! interpolate_integer {
+    ($t:ty) => {
+        impl Interpolate for $t {
+            fn weighted_sum(values: &[(Self, f64)]) -> Self {
+                let sum: f64 = values.iter().map(|&(v, w)| v as f64 * w).sum();
+                sum.round().max(<$t>::MIN as f64).min(<$t>::MAX as f64) as $t
+            }
+        }
+    }
+}
+interpolate_integer!(u8);
+interpolate_integer!(u16);
+interpolate_integer!(u32);
+interpolate_integer!(i8);
+interpolate_integer!(i16);
+interpolate_integer!(i32);
+
+macro_rules! interpolate_array {
+    ($n:expr) => {
+        impl<T: Interpolate + Copy> Interpolate for [T; $n] {
+            fn weighted_sum(values: &[(Self, f64)]) -> Self {
+                let mut result = values[0].0;
+                let mut channel = Vec::with_capacity(values.len());
+                for (c, result) in result.iter_mut().enumerate() {
+                    channel.clear();
+                    channel.extend(values.iter().map(|&(v, w)| (v[c], w)));
+                    *result = T::weighted_sum(&channel);
+                }
+                result
+            }
+        }
+    }
+}
+interpolate_array!(2);
+interpolate_array!(3);
+interpolate_array!(4);
+
+/// An equirectangular (plate carrée) image of the whole world, stored row-major like
+/// `OrthoProj`. The top row is at the north pole, the bottom at the south pole, the left column is
+/// at 180°W, & the right at 180°E.
+#[derive(Clone, Copy, Debug)]
+pub struct Equirectangular<'a, T: 'a> {
+    data: &'a [T],
+    width: u32,
+    height: u32,
+}
+
+impl<'a, T: Clone> Equirectangular<'a, T> {
+    /// An equirectangular image of `width` by `height` pixels. Panics if `data` doesn't have
+    /// `width * height` values, or either is 0.
+    pub fn new(data: &'a [T], width: u32, height: u32) -> Self {
+        assert!(width > 0 && height > 0, "equirectangular image must have some pixels");
+        assert_eq!(data.len(), width as usize * height as usize, "data must have width * height values");
+        Equirectangular{ data, width, height }
+    }
+
+    /// The value of the pixel `lat`/`lon` is in
+    pub fn get<F: Float>(&self, lat: F, lon: F) -> &T {
+        let (x, y) = self.xy_for_pos(lat, lon);
+        let (x, y) = self.reflect(x, (y + 0.5).floor() as i64);
+        self.pixel((x + 0.5).floor() as i64, y)
+    }
+
+    /// The value at `lat`/`lon`, mixing the pixels around it with `sampling`. Pixels past the
+    /// 180° line, and past the poles, come from the other side of the world, so there are no
+    /// seams.
+    pub fn sample<F: Float>(&self, lat: F, lon: F, sampling: Sampling) -> T
+        where T: Interpolate
+    {
+        let (x, y) = self.xy_for_pos(lat, lon);
+        let y0 = y.floor();
+        let ty = y - y0;
+        let y0 = y0 as i64;
+
+        // Each row is looked up separately, since rows past the poles are from half way around
+        // the world, which isn't a whole number of pixels for odd widths
+        match sampling {
+            Sampling::Nearest => self.get(lat, lon).clone(),
+            Sampling::Bilinear => {
+                let mut values = Vec::with_capacity(4);
+                for &(y, wy) in &[(y0, 1. - ty), (y0 + 1, ty)] {
+                    let (x, y) = self.reflect(x, y);
+                    let x0 = x.floor();
+                    let tx = x - x0;
+                    values.push((self.pixel(x0 as i64, y).clone(), (1. - tx) * wy));
+                    values.push((self.pixel(x0 as i64 + 1, y).clone(), tx * wy));
+                }
+                T::weighted_sum(&values)
+            },
+            Sampling::Bicubic => {
+                let mut values = Vec::with_capacity(16);
+                for (j, wy) in catmull_rom(ty).iter().enumerate() {
+                    let (x, y) = self.reflect(x, y0 + j as i64 - 1);
+                    let x0 = x.floor();
+                    for (i, wx) in catmull_rom(x - x0).iter().enumerate() {
+                        values.push((self.pixel(x0 as i64 + i as i64 - 1, y).clone(), wx * wy));
+                    }
+                }
+                T::weighted_sum(&values)
+            },
+        }
+    }
+
+    /// Where `lat`/`lon` is on this image, in pixels, where whole numbers are the centres of
+    /// pixels.
+    fn xy_for_pos<F: Float>(&self, lat: F, lon: F) -> (f64, f64) {
+        let x = (lon.to_f64() + 180.) / 360. * self.width as f64 - 0.5;
+        let y = (90. - lat.to_f64()) / 180. * self.height as f64 - 0.5;
+        (x, y)
+    }
+
+    /// The row `y` is, & where `x` (in pixels) is along it. Past the poles is the other side of
+    /// the world, half way around, which is half a pixel off a whole column for odd widths, so
+    /// this is done before picking a column.
+    fn reflect(&self, x: f64, y: i64) -> (f64, i64) {
+        let h = self.height as i64;
+        let half = self.width as f64 / 2.;
+        if y < 0 {
+            (x + half, -1 - y)
+        } else if y >= h {
+            (x + half, 2*h - 1 - y)
+        } else {
+            (x, y)
+        }
+    }
+
+    /// The pixel at `x`, `y`, going around the world when off the left or right. Rows past the
+    /// poles should be `reflect`ed first.
+    fn pixel(&self, x: i64, y: i64) -> &T {
+        let (w, h) = (self.width as i64, self.height as i64);
+        // Very tall images might need more than one reflection, but that's only for points which
+        // are a whole image height away, which doesn't happen.
+        let y = y.max(0).min(h - 1);
+        let x = x.rem_euclid(w);
+        &self.data[(y * w + x) as usize]
+    }
+}
+
+/// The Catmull-Rom weights for the 4 pixels around a point `t` (0 to 1) of the way between the
+/// middle 2.
+pub(crate) fn catmull_rom(t: f64) -> [f64; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.*t2 - t),
+        0.5 * (3.*t3 - 5.*t2 + 2.),
+        0.5 * (-3.*t3 + 4.*t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+impl<T: Clone> OrthoProj<T> {
+    /// Create a new OrthoProj, `size` and `lon`/`lat`, showing the equirectangular (plate
+    /// carrée) world image `src`, which is `width` by `height` & row-major. Each pixel on the
+    /// globe is the closest pixel in `src`. Pixels off the globe are `bg`.
+    ///
+    /// Use `from_equirectangular_with` for smoother sampling.
+    pub fn from_equirectangular<F: Float>(src: &[T], width: u32, height: u32, size: u32, lat: F, lon: F, bg: T) -> Self {
+        let src = Equirectangular::new(src, width, height);
+        Self::from_fn_f64(size, lat, lon, bg, |lat, lon| src.get(lat, lon).clone())
+    }
+}
+
+impl<T: Interpolate> OrthoProj<T> {
+    /// Create a new OrthoProj, `size` and `lon`/`lat`, showing the equirectangular world image
+    /// `src`, getting the value of each pixel on the globe with `sampling`. Pixels off the globe
+    /// are `bg`.
+    ///
+    ///```
+    ///# use orthoproj::{OrthoProj, Equirectangular, Sampling};
+    ///// A world map which is red at the north pole, & blue at the south pole
+    ///let world: Vec<[u8; 3]> = (0..180*360).map(|i| [(255 - i / 360 * 255 / 180) as u8, 0, (i / 360 * 255 / 180) as u8]).collect();
+    ///let world = Equirectangular::new(&world, 360, 180);
+    ///let globe = OrthoProj::from_equirectangular_with(&world, 500, 41.89889, 12.47337, [0, 0, 0], Sampling::Bilinear);
+    ///```
+    pub fn from_equirectangular_with<F: Float>(src: &Equirectangular<T>, size: u32, lat: F, lon: F, bg: T, sampling: Sampling) -> Self {
+        Self::from_fn_f64(size, lat, lon, bg, |lat, lon| src.sample(lat, lon, sampling))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Equirectangular, Interpolate, Sampling};
+    use OrthoProj;
+
+    /// A 36x18 image where each pixel is its own index
+    fn index_image() -> Vec<u32> {
+        (0..36*18).collect()
+    }
+
+    #[test]
+    fn test_get() {
+        let data = index_image();
+        let src = Equirectangular::new(&data, 36, 18);
+        // Pixels are 10° square
+        assert_eq!(src.get(85., -175.), &0);
+        assert_eq!(src.get(89., -179.), &0);
+        assert_eq!(src.get(85., 175.), &35);
+        assert_eq!(src.get(-85., 175.), &(36*18 - 1));
+        assert_eq!(src.get(5., 5.), &(8*36 + 18));
+        assert_eq!(src.get(-5., -5.), &(9*36 + 17));
+        // Wraps around
+        assert_eq!(src.get(85., 185.), &0);
+    }
+
+    #[test]
+    fn test_weighted_sum() {
+        assert_eq!(f64::weighted_sum(&[(1., 0.5), (3., 0.5)]), 2.);
+        assert_eq!(u8::weighted_sum(&[(250, 1.5), (0, -0.5)]), 255);
+        assert_eq!(u8::weighted_sum(&[(10, 1.5), (100, -0.5)]), 0);
+        assert_eq!(u32::weighted_sum(&[(10, 0.5), (21, 0.5)]), 16);
+        assert_eq!(i32::weighted_sum(&[(-10, 1.5), (100, -0.5)]), -65);
+        assert_eq!(<[u8; 3]>::weighted_sum(&[([0, 10, 20], 0.25), ([100, 50, 20], 0.75)]), [75, 40, 20]);
+    }
+
+    #[test]
+    fn test_sample() {
+        // Value is the longitude, for the middle of each pixel
+        let data: Vec<f64> = (0..18*36).map(|i| (i % 36) as f64 * 10. - 175.).collect();
+        let src = Equirectangular::new(&data, 36, 18);
+
+        for &sampling in &[Sampling::Nearest, Sampling::Bilinear, Sampling::Bicubic] {
+            assert_eq!(src.sample(45., 5., sampling), 5.);
+            assert_eq!(src.sample(-45., -175., sampling), -175.);
+        }
+        assert_eq!(src.sample(45., 7., Sampling::Nearest), 5.);
+        assert!((src.sample(45., 7., Sampling::Bilinear) - 7.).abs() < 1e-6);
+        assert!((src.sample(45., 7., Sampling::Bicubic) - 7.).abs() < 1e-6);
+
+        // Across the 180° line, it's between the first & last columns
+        assert!((src.sample(45., 180., Sampling::Bilinear) - 0.).abs() < 1e-6);
+        assert!((src.sample(45., -180., Sampling::Bilinear) - 0.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sample_poles() {
+        // Value is the latitude, for the middle of each pixel
+        let data: Vec<f64> = (0..18*36).map(|i| 85. - (i / 36) as f64 * 10.).collect();
+        let src = Equirectangular::new(&data, 36, 18);
+
+        assert!((src.sample(40., 20., Sampling::Bilinear) - 40.).abs() < 1e-6);
+        assert!((src.sample(40., 20., Sampling::Bicubic) - 40.).abs() < 1e-6);
+
+        // Past the top row, it's the top row on the other side of the world, so it's flat
+        assert_eq!(src.sample(90., 20., Sampling::Bilinear), 85.);
+        assert_eq!(src.sample(-90., 20., Sampling::Bilinear), -85.);
+
+        // Just past the pole is the other side of the world. The western half of the top row is
+        // 100, the rest 0
+        let data: Vec<u8> = (0..18*36).map(|i| if i < 18 { 100 } else { 0 }).collect();
+        let src = Equirectangular::new(&data, 36, 18);
+        assert_eq!(src.get(89., -90.), &100);
+        assert_eq!(src.sample(89., -90., Sampling::Bilinear), 60);
+        assert_eq!(src.sample(89., 90., Sampling::Bilinear), 40);
+        assert_eq!(src.sample(-89., -90., Sampling::Bilinear), 0);
+
+        // With an odd width, the other side of the world is half way between two columns
+        let data = [0., 1., 2., 0., 0., 0.];
+        let src = Equirectangular::new(&data, 3, 2);
+        // Half way between the top row at -120° (0), & the top row at 60° (1.5)
+        assert_eq!(src.sample(90., -120., Sampling::Bilinear), 0.75);
+        // Half way between the top row at 0° (1), & the top row at 180° (1, between 2 & 0)
+        assert_eq!(src.sample(90., 0., Sampling::Bilinear), 1.);
+    }
+
+    #[test]
+    fn test_from_equirectangular() {
+        // Northern hemisphere is 1, southern is 2
+        let data: Vec<u8> = (0..18*36).map(|i| if i < 9*36 { 1 } else { 2 }).collect();
+        let o = OrthoProj::from_equirectangular(&data, 36, 18, 100, 0., 0., 0);
+        assert_eq!(o.get_pixel(0, 0), &0);
+        assert_eq!(o.get(45., 0.), Some(&1));
+        assert_eq!(o.get(-45., 80.), Some(&2));
+
+        let data: Vec<[u8; 3]> = (0..18*36).map(|i| [(i % 36 * 7) as u8, 0, 0]).collect();
+        let src = Equirectangular::new(&data, 36, 18);
+        let o = OrthoProj::from_equirectangular_with(&src, 100, 0., 180., [0, 0, 255], Sampling::Bicubic);
+        assert_eq!(o.get_pixel(0, 0), &[0, 0, 255]);
+        // At 180°, it's between the first (0) & last (245) columns, not a seam
+        let (x, y) = o.xy_for_pos(0., 180.).unwrap();
+        let (lat, lon) = o.pos_for_xy(x, y).unwrap();
+        assert_eq!(o.get_pixel(x, y), &src.sample(lat, lon, Sampling::Bicubic));
+        assert!((60..190).contains(&o.get_pixel(x, y)[0]));
+    }
+}
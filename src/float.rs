@@ -0,0 +1,21 @@
+//! Lat/lon & pixel distances, which can be `f32` or `f64`
+
+/// A latitude, longitude, or distance in pixels. This is implemented for `f32` & `f64`, so
+/// either can be passed in. Everything is worked out with `f64`, so `f64` values keep their full
+/// precision, which matters for very large images.
+pub trait Float: Copy {
+    /// This value as an `f64`
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
@@ -0,0 +1,225 @@
+//! Drawing lines of latitude & longitude on an `OrthoProj`
+
+use {Float, OrthoProj, Projection};
+use sphere::{self, Vec3};
+
+/// How far the tropics are from the equator, in degrees. This is the tilt of the Earth's axis,
+/// which changes very slowly; this is the value for the year 2000.
+const TROPIC_LAT: f64 = 23.43928;
+
+/// The smallest step between graticule lines, in degrees. That's still 36,000 meridians, & any
+/// less would just be more lines than pixels, or run out of memory.
+const MIN_STEP: f64 = 0.01;
+
+/// Lines of latitude which are often drawn differently to the rest of the graticule, with
+/// `OrthoProj::draw_special_parallel`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecialParallel {
+    /// 0°
+    Equator,
+    /// The tropics of Cancer & Capricorn, 23.44° north & south
+    Tropics,
+    /// The Arctic & Antarctic circles, 66.56° north & south
+    PolarCircles,
+}
+
+impl SpecialParallel {
+    /// The latitudes (in degrees) of these lines
+    pub fn latitudes(&self) -> &'static [f64] {
+        match *self {
+            SpecialParallel::Equator => &[0.],
+            SpecialParallel::Tropics => &[TROPIC_LAT, -TROPIC_LAT],
+            SpecialParallel::PolarCircles => &[90. - TROPIC_LAT, TROPIC_LAT - 90.],
+        }
+    }
+}
+
+impl<T: Clone, P: Projection> OrthoProj<T, P> {
+    /// Draw lines of latitude every `lat_step` degrees north & south of the equator, and lines
+    /// of longitude every `lon_step` degrees east & west of the prime meridian, setting those
+    /// pixels to `value`. The lines have no gaps, and stop at the edge of the globe, like
+    /// `draw_linestring`. A step of 0 leaves out those lines, as does an infinite one, or one under
+    /// 0.01°.
+    ///
+    /// To draw some lines differently, draw them on top with `draw_special_parallel`,
+    /// `draw_parallel` or `draw_meridian`.
+    ///
+    ///```
+    ///use orthoproj::{OrthoProj, SpecialParallel};
+    ///let mut image = OrthoProj::new_with_bg(500, 41.89889, 12.47337, 0u8, 1);
+    ///image.draw_graticule(15., 15., 2);
+    ///image.draw_special_parallel(SpecialParallel::Equator, 3);
+    ///image.draw_special_parallel(SpecialParallel::Tropics, 4);
+    ///assert_eq!(image.get(0., 10.), Some(&3));
+    ///assert_eq!(image.get(30., 10.), Some(&2));
+    ///```
+    pub fn draw_graticule<F: Float>(&mut self, lat_step: F, lon_step: F, value: T) {
+        for lon in meridians(lon_step.to_f64()) {
+            self.draw_meridian(lon, value.clone());
+        }
+        for lat in parallels(lat_step.to_f64()) {
+            self.draw_parallel(lat, value.clone());
+        }
+    }
+
+    /// Draw the line of latitude `lat` (in degrees), all the way around the globe, setting those
+    /// pixels to `value`.
+    pub fn draw_parallel<F: Float>(&mut self, lat: F, value: T) {
+        let points: Vec<_> = parallel(lat.to_f64()).into_iter().map(sphere::to_pos).collect();
+        self.draw_linestring(&points, value);
+    }
+
+    /// Draw the line of longitude `lon` (in degrees), from pole to pole, setting those pixels to
+    /// `value`.
+    pub fn draw_meridian<F: Float>(&mut self, lon: F, value: T) {
+        let points: Vec<_> = meridian(lon.to_f64()).iter().map(|&p| sphere::to_pos(p)).collect();
+        self.draw_linestring(&points, value);
+    }
+
+    /// Draw `parallel` (e.g. both tropics), setting those pixels to `value`.
+    pub fn draw_special_parallel(&mut self, parallel: SpecialParallel, value: T) {
+        for &lat in parallel.latitudes() {
+            self.draw_parallel(lat, value.clone());
+        }
+    }
+}
+
+/// The longitudes of the meridians every `step` degrees east & west of the prime meridian, as
+/// drawn by `draw_graticule`. -180° is left out, since it's the same line as 180°.
+pub fn meridians(step: f64) -> Vec<f64> {
+    steps(step, 180.).into_iter().filter(|&lon| lon > -180.).collect()
+}
+
+/// The latitudes of the parallels every `step` degrees north & south of the equator, as drawn by
+/// `draw_graticule`. The poles are left out, since they're just a point.
+pub fn parallels(step: f64) -> Vec<f64> {
+    steps(step, 90.).into_iter().filter(|&lat| lat.abs() < 90.).collect()
+}
+
+/// Points along the line of longitude `lon`, from the south pole to the north pole
+pub fn meridian(lon: f64) -> [Vec3; 3] {
+    [sphere::from_pos(-90., lon), sphere::from_pos(0., lon), sphere::from_pos(90., lon)]
+}
+
+/// Points along the line of latitude `lat`, all the way around, ending where it starts
+pub fn parallel(lat: f64) -> Vec<Vec3> {
+    let mut points = sphere::small_circle(sphere::from_pos(90., 0.), (90. - lat).to_radians());
+    points.push(points[0]);
+    points
+}
+
+/// Every multiple of `step` from `-limit` to `limit`, including 0. None if `step` is less than
+/// `MIN_STEP`, or isn't finite.
+fn steps(step: f64, limit: f64) -> Vec<f64> {
+    if step < MIN_STEP || !step.is_finite() {
+        return Vec::new();
+    }
+    let n = (limit / step).floor() as i64;
+    (-n..=n).map(|i| i as f64 * step).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {PlateCarree, Stereographic};
+    use test_helpers::count;
+
+    #[test]
+    fn test_steps() {
+        assert_eq!(steps(30., 90.), vec![-90., -60., -30., 0., 30., 60., 90.]);
+        assert_eq!(steps(40., 90.), vec![-80., -40., 0., 40., 80.]);
+        assert_eq!(steps(0., 90.), Vec::<f64>::new());
+        assert_eq!(steps(-10., 90.), Vec::<f64>::new());
+        assert_eq!(steps(f64::NAN, 90.), Vec::<f64>::new());
+        assert_eq!(steps(f64::INFINITY, 90.), Vec::<f64>::new());
+        // Rather than running out of memory
+        assert_eq!(steps(1e-300, 90.), Vec::<f64>::new());
+        assert_eq!(steps(MIN_STEP / 2., 90.), Vec::<f64>::new());
+        assert_eq!(steps(MIN_STEP, 180.).len(), 36_001);
+        assert_eq!(meridians(90.), vec![-90., 0., 90., 180.]);
+        assert_eq!(parallels(40.), vec![-80., -40., 0., 40., 80.]);
+        assert_eq!(parallels(45.), vec![-45., 0., 45.]);
+    }
+
+    #[test]
+    fn test_draw_graticule() {
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_graticule(30., 45., 1);
+        for &(lat, lon) in &[(0., 10.), (0., -70.), (30., 20.), (-60., 5.), (20., 45.), (50., -45.), (10., 0.)] {
+            assert_eq!(o.get(lat, lon), Some(&1), "{} {}", lat, lon);
+        }
+        // In between them
+        for &(lat, lon) in &[(15., 20.), (-45., 20.), (45., -20.), (10., 60.)] {
+            assert_eq!(o.get(lat, lon), Some(&0), "{} {}", lat, lon);
+        }
+        // Nothing around the back, or off the globe
+        assert_eq!(o.get_pixel(0, 0), &0);
+        assert_eq!(o.get_pixel(199, 199), &0);
+
+        // The lines are continuous, so the equator & prime meridian make a cross through the
+        // whole globe
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_graticule(90., 180., 1);
+        assert_eq!(count(&o, 1), 200 + 200 - 1);
+
+        // Only meridians, or only parallels
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_graticule(0., 30., 1);
+        assert_eq!(o.get(0., 10.), Some(&0));
+        assert_eq!(o.get(10., 30.), Some(&1));
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_graticule(30., 0., 1);
+        assert_eq!(o.get(30., 10.), Some(&1));
+        assert_eq!(o.get(10., 0.), Some(&0));
+
+        // Tiny steps leave the lines out, rather than running out of memory
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_graticule(1e-300, 1e-300, 1);
+        assert_eq!(count(&o, 1), 0);
+    }
+
+    #[test]
+    fn test_special_parallels() {
+        let mut o = OrthoProj::new(400, 30., 0., 0u8);
+        o.draw_graticule(10., 10., 1);
+        o.draw_special_parallel(SpecialParallel::Equator, 2);
+        o.draw_special_parallel(SpecialParallel::Tropics, 3);
+        o.draw_special_parallel(SpecialParallel::PolarCircles, 4);
+        assert_eq!(o.get(0., 5.), Some(&2));
+        assert_eq!(o.get(23.43928, 5.), Some(&3));
+        assert_eq!(o.get(-23.43928, 5.), Some(&3));
+        assert_eq!(o.get(66.56072, 5.), Some(&4));
+        assert_eq!(o.get(20., 5.), Some(&1));
+        assert_eq!(o.get(-66.56072, 5.), None);
+
+        // Drawn on top, where the meridians cross them
+        assert_eq!(o.get(0., 10.), Some(&2));
+        assert_eq!(o.get(1., 10.), Some(&1));
+        assert!(count(&o, 2) > 100);
+
+        o.draw_meridian(5., 5);
+        assert_eq!(o.get(40., 5.), Some(&5));
+        o.draw_parallel(45., 6);
+        assert_eq!(o.get(45., 7.), Some(&6));
+    }
+
+    #[test]
+    fn test_graticule_projections() {
+        // Cut at the edge of a world map, which is where the 180° meridian is
+        let mut o = OrthoProj::new_with_projection(360, 180, PlateCarree::new(0.), 0u8);
+        o.draw_graticule(30., 30., 1);
+        assert_eq!(o.get(0., 100.), Some(&1));
+        assert_eq!(o.get(30.5, 100.), Some(&1));
+        assert_eq!(o.get(15., 100.), Some(&0));
+        assert_eq!(o.get_pixel(0, 50), &0);
+        // Every row has the other 11 meridians
+        assert!(o.rows().all(|row| row.iter().filter(|&&v| v == 1).count() >= 11));
+
+        // Parallels are circles around the centre of a polar map
+        let mut o = OrthoProj::new_with_projection(200, 200, Stereographic::new(90., 0.), 0u8);
+        o.draw_graticule(30., 0., 1);
+        for i in 0..36 {
+            assert_eq!(o.get(60., i as f64 * 10.), Some(&1));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Converting between `OrthoProj` and the `image` crate's `ImageBuffer`, with the `image` feature
+
+use std::ops::Deref;
+
+use image::{ImageBuffer, Pixel};
+
+use {Float, OrthoProj, Projection};
+
+impl<P: Pixel> OrthoProj<P> {
+    /// Create a new OrthoProj, with the same size & pixels as `buf`, centred on `lat` and `lon`.
+    /// The globe is in the middle of the image, and as large as will fit.
+    ///
+    ///```
+    ///# extern crate image;
+    ///# extern crate orthoproj;
+    ///# use orthoproj::OrthoProj;
+    ///use image::{Rgb, RgbImage};
+    ///let background = RgbImage::from_pixel(640, 480, Rgb([0, 0, 20]));
+    ///let mut globe = OrthoProj::from_image_buffer(&background, 41.89889, 12.47337);
+    ///globe.fill_globe(Rgb([0, 0, 255]));
+    ///let image: RgbImage = globe.into();
+    ///```
+    pub fn from_image_buffer<C, F>(buf: &ImageBuffer<P, C>, lat: F, lon: F) -> Self
+        where C: Deref<Target=[P::Subpixel]>, F: Float
+    {
+        let data = buf.pixels().cloned().collect();
+        OrthoProj::from_vec(buf.width(), buf.height(), lat, lon, data)
+    }
+}
+
+impl<P: Pixel, Proj: Projection> From<OrthoProj<P, Proj>> for ImageBuffer<P, Vec<P::Subpixel>> {
+    fn from(o: OrthoProj<P, Proj>) -> Self {
+        let (width, height) = (o.width(), o.height());
+        let mut data = Vec::with_capacity(width as usize * height as usize * P::CHANNEL_COUNT as usize);
+        for pixel in o.iter() {
+            data.extend_from_slice(pixel.channels());
+        }
+        ImageBuffer::from_raw(width, height, data).expect("OrthoProj has width * height pixels")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+    use OrthoProj;
+
+    #[test]
+    fn test_image_buffer() {
+        let mut o = OrthoProj::new_with_dimensions(30, 20, 0., 0., Rgba([0u8, 0, 0, 0]));
+        o.set(0., 0., Rgba([1, 2, 3, 4]));
+        o.set_pixel(0, 19, Rgba([5, 6, 7, 8]));
+        let img: RgbaImage = o.into();
+        assert_eq!(img.dimensions(), (30, 20));
+        assert_eq!(img.get_pixel(15, 10), &Rgba([1, 2, 3, 4]));
+        assert_eq!(img.get_pixel(0, 19), &Rgba([5, 6, 7, 8]));
+        assert_eq!(img.get_pixel(19, 0), &Rgba([0, 0, 0, 0]));
+
+        let o = OrthoProj::from_image_buffer(&img, 0., 0.);
+        assert_eq!((o.width(), o.height()), (30, 20));
+        assert_eq!(o.get(0., 0.), Some(&Rgba([1, 2, 3, 4])));
+        assert_eq!(o.get_pixel(0, 19), &Rgba([5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_gray_image() {
+        let img = GrayImage::from_fn(5, 4, |x, y| Luma([(10*y + x) as u8]));
+        let o = OrthoProj::from_image_buffer(&img, 10., 10.);
+        assert_eq!(o.get_pixel(3, 2), &Luma([23]));
+        let img2: GrayImage = o.into();
+        assert_eq!(img, img2);
+    }
+}
@@ -1,9 +1,50 @@
 //! Create orthographic projection images in Rust
 
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(feature = "png")]
+extern crate png;
+
+mod antialias;
+mod blend;
+mod clip;
+mod draw;
+mod ellipsoid;
+mod equirectangular;
+mod float;
+mod graticule;
+mod marker;
+#[cfg(feature = "image")]
+mod image_buffer;
+mod pixel;
+#[cfg(feature = "png")]
+mod png_image;
+mod pnm;
+mod projection;
+mod sphere;
+pub mod svg;
+#[cfg(test)]
+mod test_helpers;
+mod world;
+
+pub use blend::{Blend, BlendMode};
+pub use ellipsoid::Ellipsoid;
+pub use equirectangular::{Equirectangular, Interpolate, Sampling};
+pub use float::Float;
+pub use graticule::SpecialParallel;
+pub use marker::Marker;
+pub use pixel::Pixel;
+pub use projection::{AzimuthalEquidistant, Boundary, Gnomonic, LambertAzimuthalEqualArea, Orthographic, Perspective, Projection, Stereographic};
+pub use world::{Mollweide, PlateCarree, Robinson, WebMercator, WinkelTripel};
+
+use std::f64::consts::PI;
+
+use sphere::Vec3;
+
 /// An orthographic image
 /// 
-/// Images are square, with the globe in the middle. Create one with a size of 500x500, where
-/// all pixels are set to `0`, centred on Rome:
+/// By default images are square, with the globe in the middle. Create one with a size of
+/// 500x500, where all pixels are set to `0`, centred on Rome:
 ///
 /// # Examples
 ///
@@ -20,115 +61,704 @@
 ///image.set(51.50791, -0.12786, 1);
 ///```
 ///
+/// Images can also be rectangular, with the globe any size, anywhere on the image. This is a
+/// 1920x1080 frame, with a globe of radius 400 pixels, moved 300 pixels right of the centre:
+///
+///```
+///# use orthoproj::OrthoProj;
+///let mut image = OrthoProj::new_with_dimensions(1920, 1080, 41.89889, 12.47337, 0)
+///    .with_radius(400.)
+///    .with_offset(300., 0.);
+///image.fill_globe(1);
+///```
+///
 /// You can then loop over all the pixels, getting the current value.
 ///
-pub struct OrthoProj<T: Clone> {
+///```
+///# use orthoproj::OrthoProj;
+///# let mut image = OrthoProj::new(500, 41.89889, 12.47337, 0);
+///# image.set(51.50791, -0.12786, 1);
+///for (x, y, value) in image.enumerate_pixels() {
+///    println!("({}, {}) is {}", x, y, value);
+///}
+///```
+///
+/// Or loop over them with the lat/lon of each pixel, which is `None` for pixels off the globe.
+/// This fills the northern hemisphere with `2`:
+///
+///```
+///# use orthoproj::OrthoProj;
+///# let mut image = OrthoProj::new(500, 41.89889, 12.47337, 0);
+///for (_x, _y, pos, value) in image.iter_geo_mut() {
+///    if let Some((lat, _lon)) = pos {
+///        if lat > 0. {
+///            *value = 2;
+///        }
+///    }
+///}
+///```
+///
+/// Pixels are stored row-major: one row at a time from the top, and left to right in each row,
+/// so pixel (`x`, `y`) is at index `y * width + x` of `as_slice()`. This is the layout most
+/// image encoders expect.
+///
+///```
+///# use orthoproj::OrthoProj;
+///let mut image = OrthoProj::new_with_bg(500, 41.89889, 12.47337, 0u8, 1);
+///let pixels: &[u8] = image.as_slice();
+///assert_eq!(pixels.len(), 500*500);
+///assert_eq!(pixels[250*500 + 250], 1);
+///```
+///
+pub struct OrthoProj<T: Clone, P: Projection = Orthographic> {
     _data: Vec<T>,
-    _lat: f32,
-    _lon: f32,
-    _size: u32,
+    _width: u32,
+    _height: u32,
+    _view: View<P>,
+}
+
+/// Where the globe is on the image, and which part of it we're looking at. This is kept apart
+/// from the pixels so we can project while changing pixels.
+#[derive(Clone, Debug)]
+struct View<P: Projection> {
+    projection: P,
+    boundary: Boundary,
+    /// Radius of the globe, in pixels
+    radius: f64,
+    /// Where the centre of the map is, in pixels
+    cx: f64,
+    cy: f64,
+    /// The sin & cos of how far the map is turned anticlockwise on the image
+    rotation: (f64, f64),
+    /// The point at the centre of the map, and which way is east & north from there
+    centre: Vec3,
+    east: Vec3,
+    north: Vec3,
+    /// For `Boundary::Circle`, points are on the map when their dot product with `centre` is at
+    /// least this
+    min_dot: f64,
+    /// How much the map is stretched, at most, compared to the globe
+    max_scale: f64,
+    /// How long the edge of the map is, at most, for each radian of `edge_point`'s angle
+    edge_scale: f64,
+}
+
+/// On world maps, points this close (in degrees) to the meridian opposite the centre are counted
+/// as off the map, so that there's a (very thin) gap for lines to be cut at.
+const SEAM: f64 = 1e-7;
+
+/// Mean radius of the Earth, in km
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Points on the image this close (in pixels) to a pixel edge are counted as on it, so that
+/// rounding in the maths (e.g. 50 × sin 30° is 24.999…) doesn't move them into the pixel before.
+const SNAP: f64 = 1e-9;
+
+/// The pixel the continuous image coordinate `v` is in, as a whole number
+fn pixel_floor(v: f64) -> f64 {
+    let nearest = v.round();
+    if (v - nearest).abs() < SNAP { nearest } else { v.floor() }
+}
+
+impl<P: Projection> View<P> {
+    /// The view for a `width` by `height` image, with the map in the middle, as large as will
+    /// fit.
+    fn new(width: u32, height: u32, projection: P) -> Self {
+        let (lat, lon) = projection.centre();
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+        let (extent_x, extent_y) = projection.extent();
+        let boundary = projection.boundary();
+        let mut view = View{
+            radius: (width as f64 / 2. / extent_x).min(height as f64 / 2. / extent_y),
+            cx: width as f64 / 2., cy: height as f64 / 2.,
+            rotation: (0., 1.),
+            centre: sphere::from_pos(lat, lon),
+            east: [-sin_lon, cos_lon, 0.],
+            north: [-sin_lat*cos_lon, -sin_lat*sin_lon, cos_lat],
+            min_dot: match boundary {
+                Boundary::Circle(angle) => angle.to_radians().cos(),
+                Boundary::Antimeridian(_) => -1.,
+            },
+            max_scale: 1.,
+            edge_scale: 1.,
+            boundary,
+            projection,
+        };
+        view.max_scale = view.find_max_scale();
+        view.edge_scale = view.find_edge_scale();
+        view
+    }
+
+    /// Where on the image `lat`/`lon` is, as a continuous (not whole pixel) value. `None` if it's
+    /// not on the map.
+    fn xy_for_pos(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (x, y) = self.projection.forward(lat, lon)?;
+        if !x.is_finite() || !y.is_finite() {
+            // e.g. NaN lat/lon
+            return None;
+        }
+        Some(self.image_xy(x, y))
+    }
+
+    /// What lat/lon is at the continuous point `x`, `y` on the image. `None` if it's not on the
+    /// map.
+    fn pos_for_xy(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.radius <= 0. {
+            return None;
+        }
+        // y goes north, but image rows go down
+        let (x, y) = ((x - self.cx) / self.radius, -(y - self.cy) / self.radius);
+        let (sin, cos) = self.rotation;
+        let (x, y) = (x*cos + y*sin, y*cos - x*sin);
+        self.projection.inverse(x, y)
+    }
+
+    /// The lat/lon of the centre of pixel `x`, `y`.
+    fn pos_for_pixel(&self, x: u32, y: u32) -> Option<(f64, f64)> {
+        self.pos_for_xy(x as f64 + 0.5, y as f64 + 0.5)
+    }
+
+    /// Is the point `p` on the map
+    fn visible(&self, p: Vec3) -> bool {
+        match self.boundary {
+            Boundary::Circle(_) => sphere::dot(p, self.centre) >= self.min_dot,
+            Boundary::Antimeridian(max_lat) => {
+                let (lat, lon) = sphere::to_pos(p);
+                lat.abs() <= max_lat && self.delta_lon(lon).abs() <= 180. - SEAM
+            },
+        }
+    }
+
+    /// Where on the image the point `p` is, as a continuous value. `p` must be `visible`.
+    fn xy_for_vec(&self, p: Vec3) -> (f64, f64) {
+        let (lat, lon) = sphere::to_pos(p);
+        let (x, y) = match self.projection.forward(lat, lon) {
+            Some(xy) => xy,
+            None => {
+                // Right on the edge, rounding can put it just off the map
+                let (lat, lon) = sphere::to_pos(self.edge_point(self.angle_of(p)));
+                self.projection.forward(lat, lon).expect("the edge is on the map")
+            },
+        };
+        self.image_xy(x, y)
+    }
+
+    /// The point on the edge of the map (or a tiny bit inside it, so that it's always on it) at
+    /// `angle` radians anticlockwise around the edge. For `Boundary::Circle`, this is the
+    /// direction from the centre, from east. For `Boundary::Antimeridian`, it's from the south
+    /// end of the cut on the east, as a fraction of the way around the edge of the lat/lon
+    /// rectangle.
+    fn edge_point(&self, angle: f64) -> Vec3 {
+        match self.boundary {
+            Boundary::Circle(_) => self.point_at(self.min_dot.acos() - 1e-6, angle),
+            Boundary::Antimeridian(max_lat) => {
+                let (h, w) = (2. * max_lat, 360.);
+                let s = angle.rem_euclid(2. * PI) / (2. * PI) * (2. * h + 2. * w);
+                let (lat, dlon) = if s < h {
+                    (s - max_lat, 180.)
+                } else if s < h + w {
+                    (max_lat, 180. - (s - h))
+                } else if s < 2. * h + w {
+                    (max_lat - (s - h - w), -180.)
+                } else {
+                    (-max_lat, (s - 2. * h - w) - 180.)
+                };
+                let inset = 2. * SEAM;
+                let lat = lat.max(-max_lat + inset).min(max_lat - inset);
+                let dlon = dlon.max(-180. + inset).min(180. - inset);
+                sphere::from_pos(lat, self.projection.centre().1 + dlon)
+            },
+        }
+    }
+
+    /// The point `distance` radians from the centre, in the direction `angle` radians
+    /// anticlockwise from east.
+    fn point_at(&self, distance: f64, angle: f64) -> Vec3 {
+        let (sin_d, cos_d) = distance.sin_cos();
+        let (sin_a, cos_a) = angle.sin_cos();
+        let direction = sphere::add(sphere::scale(self.east, cos_a), sphere::scale(self.north, sin_a));
+        sphere::add(sphere::scale(self.centre, cos_d), sphere::scale(direction, sin_d))
+    }
+
+    /// How far around the edge of the map the point `p` is, like `edge_point`. For
+    /// `Boundary::Circle`, this is the direction `p` is from the centre, so it works for any
+    /// point. For `Boundary::Antimeridian`, it's the closest place on the edge.
+    fn angle_of(&self, p: Vec3) -> f64 {
+        match self.boundary {
+            Boundary::Circle(_) => sphere::dot(p, self.north).atan2(sphere::dot(p, self.east)),
+            Boundary::Antimeridian(max_lat) => {
+                let (lat, lon) = sphere::to_pos(p);
+                let dlon = self.delta_lon(lon);
+                let (h, w) = (2. * max_lat, 360.);
+                let sides = [
+                    (180. - dlon, lat + max_lat),
+                    (max_lat - lat, h + (180. - dlon)),
+                    (180. + dlon, h + w + (max_lat - lat)),
+                    (max_lat + lat, 2. * h + w + (dlon + 180.)),
+                ];
+                let s = sides.iter().min_by(|a, b| a.0.partial_cmp(&b.0).unwrap()).unwrap().1;
+                s / (2. * h + 2. * w) * 2. * PI
+            },
+        }
+    }
+
+    /// For world maps, if the short line from `a` to `b` crosses the meridian opposite the
+    /// centre, the point where it does, which is off the map. So lines can be cut there, even
+    /// when `a` & `b` are on either side of it, and both on the map.
+    fn seam_crossing(&self, a: Vec3, b: Vec3) -> Option<Vec3> {
+        if let Boundary::Circle(_) = self.boundary {
+            return None;
+        }
+        let side = |p: Vec3| self.delta_lon(sphere::to_pos(p).1) >= 0.;
+        let (da, db) = (self.delta_lon(sphere::to_pos(a).1), self.delta_lon(sphere::to_pos(b).1));
+        if (da >= 0.) == (db >= 0.) || (da - db).abs() <= 180. {
+            return None;
+        }
+        let (mut a, mut b) = (a, b);
+        let a_side = side(a);
+        for _ in 0..40 {
+            let mid = sphere::midpoint(a, b);
+            if side(mid) == a_side {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+        let (lat, _) = sphere::to_pos(a);
+        Some(sphere::from_pos(lat, self.projection.centre().1 + 180.))
+    }
+
+    /// How many pixels along the edge of the map `angle` radians of `edge_point` is, at most
+    fn edge_length(&self, angle: f64) -> f64 {
+        angle * self.edge_scale * self.radius
+    }
+
+    /// How far `lon` is east of the centre, in degrees, from -180 to 180
+    fn delta_lon(&self, lon: f64) -> f64 {
+        (lon - self.projection.centre().1 + 180.).rem_euclid(360.) - 180.
+    }
+
+    /// Most projections stretch the map away from the centre. Find (roughly) how much, so lines
+    /// can be split up finely enough.
+    fn find_max_scale(&self) -> f64 {
+        let max_scale = match self.boundary {
+            Boundary::Circle(_) => {
+                // Azimuthal projections are the same in every direction, so look along a line
+                // from the centre to the edge, and around circles at each point on it
+                let max_distance = self.min_dot.acos() - 1e-6;
+                let rho = |distance: f64| {
+                    let (lat, lon) = sphere::to_pos(self.point_at(distance, 0.));
+                    self.projection.forward(lat, lon).map_or(0., |(x, y)| x.hypot(y))
+                };
+                let steps = 64;
+                let mut max_scale: f64 = 1.;
+                let mut prev = 0.;
+                for i in 1..=steps {
+                    let distance = max_distance * i as f64 / steps as f64;
+                    let r = rho(distance);
+                    max_scale = max_scale.max((r - prev) / (max_distance / steps as f64));
+                    max_scale = max_scale.max(r / distance.sin());
+                    prev = r;
+                }
+                max_scale
+            },
+            Boundary::Antimeridian(max_lat) => {
+                // Look at a grid of points, and how far the map moves going a little way in a few
+                // directions from each
+                let steps = 16;
+                let d = 1e-3;
+                let mut max_scale: f64 = 1.;
+                for i in 0..=steps {
+                    for j in 0..=steps {
+                        let lat = (max_lat - 2. * d) * (2. * i as f64 / steps as f64 - 1.);
+                        let lon = self.projection.centre().1 + (180. - 2. * d) * (2. * j as f64 / steps as f64 - 1.);
+                        let (x, y) = match self.projection.forward(lat, lon) {
+                            Some(xy) => xy,
+                            None => continue,
+                        };
+                        for &(dlat, dlon) in &[(d, 0.), (0., d), (d, d), (d, -d)] {
+                            let moved = (lat + dlat, lon + dlon / lat.to_radians().cos());
+                            if let Some((x2, y2)) = self.projection.forward(moved.0, moved.1) {
+                                let distance = (dlat * dlat + dlon * dlon).sqrt().to_radians();
+                                max_scale = max_scale.max((x2 - x).hypot(y2 - y) / distance);
+                            }
+                        }
+                    }
+                }
+                max_scale
+            },
+        };
+        // Right by the point opposite the centre, some projections are infinitely stretched
+        max_scale.min(64.)
+    }
+
+    /// Find (roughly) the most the edge of the map is stretched, for each radian of
+    /// `edge_point`'s angle.
+    fn find_edge_scale(&self) -> f64 {
+        let steps = 1024;
+        let step = 2. * PI / steps as f64;
+        let xy = |i: u32| {
+            let (lat, lon) = sphere::to_pos(self.edge_point(i as f64 * step));
+            self.projection.forward(lat, lon).unwrap_or((0., 0.))
+        };
+        let mut prev = xy(0);
+        let mut max_scale: f64 = 0.;
+        for i in 1..=steps {
+            let p = xy(i);
+            max_scale = max_scale.max((p.0 - prev.0).hypot(p.1 - prev.1) / step);
+            prev = p;
+        }
+        max_scale
+    }
+
+    /// Where the point `x`, `y` on the map is on the image
+    fn image_xy(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation;
+        let (x, y) = (x*cos - y*sin, x*sin + y*cos);
+        (self.cx + self.radius*x, self.cy - self.radius*y)
+    }
 }
 
 impl<T: Clone> OrthoProj<T> {
     /// Create a new orthographic projection with width & height of `size`, centred on `lat` and
     /// `lon`. `default` is the default value
-    pub fn new(size: u32, lat: f32, lon: f32, default: T) -> Self {
-        let s = size as usize;
-        OrthoProj{ _size: size,  _data: vec![default; s*s], _lat: lat.to_radians(), _lon: lon.to_radians() }
+    pub fn new<F: Float>(size: u32, lat: F, lon: F, default: T) -> Self {
+        Self::new_with_dimensions(size, size, lat, lon, default)
+    }
+
+    /// Create a new orthographic projection which is `width` by `height`, centred on `lat` and
+    /// `lon`. `default` is the default value. The globe is in the middle of the image, and as
+    /// large as will fit.
+    pub fn new_with_dimensions<F: Float>(width: u32, height: u32, lat: F, lon: F, default: T) -> Self {
+        let len = width as usize * height as usize;
+        Self::from_vec(width, height, lat, lon, vec![default; len])
+    }
+
+    /// Create a `width` by `height` OrthoProj from these row-major pixels. `data` must have
+    /// `width * height` values.
+    fn from_vec<F: Float>(width: u32, height: u32, lat: F, lon: F, data: Vec<T>) -> Self {
+        OrthoProj::from_vec_with_projection(width, height, Orthographic::new(lat.to_f64(), lon.to_f64()), data)
     }
 
     /// Create a new OrthoProj, `size` and `lon`/`lat`, but the background (non-sphere) is `bg`,
     /// and `surface` is used for values on the sphere.
-    pub fn new_with_bg(size: u32, lat: f32, lon: f32, bg: T, surface: T) -> Self {
+    pub fn new_with_bg<F: Float>(size: u32, lat: F, lon: F, bg: T, surface: T) -> Self {
         let mut o = Self::new(size, lat, lon, bg);
+        o.fill_globe(surface);
+        o
+    }
 
-        // draw a circle
-        let r2 = (size/2)*(size/2);
-        // presume centre is at (size/2, size/2)
-        let cx = size/2;
-        let cy = size/2;
-
-        for x in 0..size {
-            for y in 0..size {
-                let dist2 = (x-cx)*(x-cx) + (y-cy)*(y-cy);
-                if dist2 <= r2 {
-                    o.set_pixel(x, y, surface.clone());
-                }
-            }
-        }
+    /// Create a new OrthoProj, `size` and `lon`/`lat`, where every pixel on the globe is `f(lat,
+    /// lon)` for the lat/lon of the centre of that pixel, and every other pixel is `bg`.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///// Northern hemisphere is 1, southern is 2, space is 0
+    ///let image = OrthoProj::from_fn(500, 41.89889, 12.47337, 0, |lat, _lon| if lat > 0. { 1 } else { 2 });
+    ///assert_eq!(image.get(51.50791, -0.12786), Some(&1));
+    ///```
+    pub fn from_fn<L, F>(size: u32, lat: L, lon: L, bg: T, mut f: F) -> Self
+        where L: Float, F: FnMut(f32, f32) -> T
+    {
+        Self::from_fn_f64(size, lat, lon, bg, |lat, lon| f(lat as f32, lon as f32))
+    }
 
+    /// Like `from_fn`, but `f` gets the lat/lon as `f64`, which is more precise than a pixel on
+    /// very large images.
+    pub fn from_fn_f64<L, F>(size: u32, lat: L, lon: L, bg: T, f: F) -> Self
+        where L: Float, F: FnMut(f64, f64) -> T
+    {
+        let mut o = Self::new(size, lat, lon, bg);
+        o.fill_with_f64(f);
         o
     }
+}
 
-    /// For this projection, what would be the pixel x/y values for this point. `None` if the
-    /// lat/lon lies outside the visible area.
-    pub fn xy_for_pos(&self, lat: f32, lon: f32) -> Option<(u32, u32)> {
-        // lat = phi
-        // lon = lambda
-        //
-        // x = r cos(lat)sin(lon - lon0)
-        // y = r ( cos(lat0)sin(lat) - sin(lat0)cos(lat)cos(lon-lon0) )
-        //
-        // cos c = sin(lat0)sin(lat) + cos(lat0)cos(lat)cos(lon-lon0)
-        // is it the far side of the globe
-        let lat = lat.to_radians();
-        let lon = lon.to_radians();
-        let cos_c = self._lat.sin() * lat.sin() + self._lat.cos()*lat.cos()*(lon - self._lon).cos();
-        if cos_c < 0. {
-            return None;
-        }
+impl<T: Clone, P: Projection> OrthoProj<T, P> {
+    /// Create a new image which is `width` by `height`, using `projection`, rather than the
+    /// orthographic projection. `default` is the default value. The map is in the middle of the
+    /// image, and as large as will fit. Everything else works the same, with "the globe" being
+    /// the part of the image the map covers.
+    ///
+    ///```
+    ///use orthoproj::{OrthoProj, Stereographic};
+    ///let mut image = OrthoProj::new_with_projection(500, 500, Stereographic::new(41.89889, 12.47337), 0);
+    ///image.set(51.50791, -0.12786, 1);
+    ///assert_eq!(image.get(51.50791, -0.12786), Some(&1));
+    ///```
+    pub fn new_with_projection(width: u32, height: u32, projection: P, default: T) -> Self {
+        let len = width as usize * height as usize;
+        Self::from_vec_with_projection(width, height, projection, vec![default; len])
+    }
+
+    /// Create a `width` by `height` OrthoProj from these row-major pixels, using `projection`.
+    /// `data` must have `width * height` values.
+    fn from_vec_with_projection(width: u32, height: u32, projection: P, data: Vec<T>) -> Self {
+        debug_assert_eq!(data.len(), width as usize * height as usize);
+        let view = View::new(width, height, projection);
+        OrthoProj{ _width: width, _height: height, _data: data, _view: view }
+    }
 
-        let r = (self._size / 2) as f32;
+    /// Change the radius of the globe to `radius` pixels. For projections other than orthographic,
+    /// this is the scale of the map, i.e. the radius of the globe it's a map of. Panics unless
+    /// `radius` is more than 0, and finite.
+    pub fn with_radius<F: Float>(mut self, radius: F) -> Self {
+        let radius = radius.to_f64();
+        assert!(radius > 0. && radius.is_finite(), "radius must be more than 0, and finite, not {}", radius);
+        self._view.radius = radius;
+        self
+    }
+
+    /// Move the centre of the globe `dx` pixels right and `dy` pixels down from the centre of the
+    /// image.
+    pub fn with_offset<F: Float>(mut self, dx: F, dy: F) -> Self {
+        self._view.cx = self._width as f64 / 2. + dx.to_f64();
+        self._view.cy = self._height as f64 / 2. + dy.to_f64();
+        self
+    }
+
+    /// Turn the globe around its centre, so that the direction `degrees` clockwise from north is
+    /// up, like the heading of a satellite. e.g. with 90, east is up & north is on the left.
+    /// The globe is still the same size, so a world map might not fit any more.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///let image = OrthoProj::new(100, 0., 0., 0u8).with_rotation(90.);
+    ///assert_eq!(image.xy_for_pos(0., 45.), Some((50, 14)));
+    ///```
+    pub fn with_rotation<F: Float>(mut self, degrees: F) -> Self {
+        self._view.rotation = degrees.to_f64().to_radians().sin_cos();
+        self
+    }
 
-        let x = r * lat.cos() * (lon - self._lon).sin();
-        let y = r * ( self._lat.cos()*lat.sin() - self._lat.sin()*lat.cos()*(lon - self._lon).cos() );
+    /// Width of the image, in pixels
+    pub fn width(&self) -> u32 {
+        self._width
+    }
 
-        // FIXME Weird hack? Why is this required?
-        let y = y * -1.;
+    /// Height of the image, in pixels
+    pub fn height(&self) -> u32 {
+        self._height
+    }
 
-        let x = x + r;
-        let y = y + r;
+    /// Radius of the globe, in pixels
+    pub fn radius(&self) -> f32 {
+        self._view.radius as f32
+    }
 
-        let x = x.trunc() as u32;
-        let y = y.trunc() as u32;
+    /// Like `radius`, but as `f64`
+    pub fn radius_f64(&self) -> f64 {
+        self._view.radius
+    }
 
-        Some((x, y))
+    /// The projection this image uses, to project points without changing the image. Its map is
+    /// scaled up by `radius()` pixels, around the centre of the globe.
+    pub fn projection(&self) -> &P {
+        &self._view.projection
+    }
+
+    /// Set every pixel on the globe to `surface`. Pixels off the globe are unchanged.
+    pub fn fill_globe(&mut self, surface: T) {
+        self.fill_with_f64(|_, _| surface.clone());
+    }
+
+    /// Set every pixel on the globe to `f(lat, lon)`, for the lat/lon of the centre of that pixel
+    /// (i.e. `pos_for_xy`). Pixels off the globe are unchanged.
+    pub fn fill_with<F>(&mut self, mut f: F)
+        where F: FnMut(f32, f32) -> T
+    {
+        self.fill_with_f64(|lat, lon| f(lat as f32, lon as f32));
+    }
+
+    /// Like `fill_with`, but `f` gets the lat/lon as `f64` (i.e. `pos_for_xy_f64`).
+    pub fn fill_with_f64<F>(&mut self, mut f: F)
+        where F: FnMut(f64, f64) -> T
+    {
+        for (_, _, pos, value) in self.iter_geo_mut_f64() {
+            if let Some((lat, lon)) = pos {
+                *value = f(lat, lon);
+            }
+        }
+    }
+
+    /// Where on the image `lat`/`lon` is, in pixels, as a continuous value rather than a whole
+    /// pixel. Pixel (`x`, `y`) covers from `x` to `x + 1` & `y` to `y + 1`, so its centre is at
+    /// (`x + 0.5`, `y + 0.5`). `None` if the lat/lon isn't on the map, e.g. it's on the far side
+    /// of the globe, but it can be off the edge of the image, or negative.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///let image = OrthoProj::new(100, 0., 0., 0u8);
+    ///assert_eq!(image.project_f(0., 0.), Some((50., 50.)));
+    ///assert_eq!(image.project_f(0., 180.), None);
+    ///```
+    pub fn project_f<F: Float>(&self, lat: F, lon: F) -> Option<(f64, f64)> {
+        self._view.xy_for_pos(lat.to_f64(), lon.to_f64())
+    }
+
+    /// For this projection, what would be the pixel x/y values for this point. `None` if the
+    /// lat/lon lies outside the visible area, either on the far side of the globe, or off the
+    /// edge of the image.
+    ///
+    /// This is the pixel `project_f` is in, i.e. it's rounded down, not to the nearest. Points a
+    /// tiny fraction of a pixel (less than 1e-9) before a pixel edge are counted as on the edge,
+    /// so rounding errors don't move exact points into the pixel before. A point exactly on the
+    /// right or bottom edge of the image is off it, so when this is `Some`, the pixel is always
+    /// in the image: `x < width()` & `y < height()`.
+    pub fn xy_for_pos<F: Float>(&self, lat: F, lon: F) -> Option<(u32, u32)> {
+        let (x, y) = self.project_f(lat, lon)?;
+        let (x, y) = (pixel_floor(x), pixel_floor(y));
+
+        // NaN isn't in either range, so is off the image too
+        if !(0. ..self._width as f64).contains(&x) || !(0. ..self._height as f64).contains(&y) {
+            return None;
+        }
+        Some((x as u32, y as u32))
+    }
+
+    /// For this projection, what is the lat/lon of the centre of pixel `x`, `y`. `None` if the
+    /// pixel is not on the globe.
+    ///
+    /// This is the inverse of `xy_for_pos`. For every pixel on the globe,
+    /// `xy_for_pos(pos_for_xy(x, y))` is that pixel again, give or take one pixel for `f32`
+    /// rounding. Use `pos_for_xy_f64` to get exactly that pixel.
+    pub fn pos_for_xy(&self, x: u32, y: u32) -> Option<(f32, f32)> {
+        self.pos_for_xy_f64(x, y).map(|(lat, lon)| (lat as f32, lon as f32))
+    }
+
+    /// Like `pos_for_xy`, but as `f64`. `f32` is only good to a few metres, which is less than a
+    /// pixel on very large images, so this is needed to get back to the same pixel.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///// No pixel values are needed to project, so don't use memory for them
+    ///let image = OrthoProj::new(16384, 41.89889, 12.47337, ());
+    ///let (lat, lon) = image.pos_for_xy_f64(12345, 6789).unwrap();
+    ///assert_eq!(image.xy_for_pos(lat, lon), Some((12345, 6789)));
+    ///```
+    pub fn pos_for_xy_f64(&self, x: u32, y: u32) -> Option<(f64, f64)> {
+        self._view.pos_for_pixel(x, y)
     }
 
     /// Set the value of `lat`, `lon` to `value`
-    pub fn set(&mut self, lat: f32, lon: f32, value: T) {
-        match self.xy_for_pos(lat, lon) {
-            None => {},
-            Some((x, y)) => {
-                self.set_pixel(x, y, value);
-            },
-        };
+    pub fn set<F: Float>(&mut self, lat: F, lon: F, value: T) {
+        if let Some(v) = self.get_mut(lat, lon) {
+            *v = value;
+        }
     }
 
-    /// For `lat`/`lon` what is the currently stored value?
-    pub fn get(&self, lat: f32, lon: f32) -> &T {
-        let r = (self._size / 2) as f32;
-        let x = r * lat.to_radians().cos() * (lon - self._lon).to_radians().sin();
-        let y = r * ( self._lon.to_radians().cos()*lat.to_radians().sin() - self._lon.to_radians().sin()*(lon - self._lat).to_radians().cos() );
-        // FIXME clipping
-        let i = x.trunc() as usize * self._size as usize + y.trunc() as usize;
+    /// For `lat`/`lon` what is the currently stored value? `None` if the lat/lon lies outside the
+    /// visible area.
+    pub fn get<F: Float>(&self, lat: F, lon: F) -> Option<&T> {
+        let (x, y) = self.xy_for_pos(lat, lon)?;
+        Some(self.get_pixel(x, y))
+    }
 
-        &self._data[i]
+    /// For `lat`/`lon`, a mutable reference to the currently stored value. `None` if the lat/lon
+    /// lies outside the visible area.
+    pub fn get_mut<F: Float>(&mut self, lat: F, lon: F) -> Option<&mut T> {
+        let (x, y) = self.xy_for_pos(lat, lon)?;
+        let i = self.index(x, y);
+        Some(&mut self._data[i])
     }
 
     /// What is the current value of pixel `x`, `y`
     pub fn get_pixel(&self, x: u32, y: u32) -> &T {
-        &self._data[(x*self._size+y) as usize]
+        &self._data[self.index(x, y)]
     }
 
     /// Shortcut to set the value of pixel (`x`, `y`) to `value`.
     fn set_pixel(&mut self, x: u32, y: u32, value: T) {
-        let i = x as usize * self._size as usize + y as usize;
+        let i = self.index(x, y);
         self._data[i] = value;
     }
+
+    /// Where in `_data` pixel (`x`, `y`) is stored.
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self._width as usize + x as usize
+    }
+
+    /// All the pixels, row-major, i.e. pixel (`x`, `y`) is at `y * width + x`.
+    pub fn as_slice(&self) -> &[T] {
+        &self._data
+    }
+
+    /// All the pixels, mutably, row-major, i.e. pixel (`x`, `y`) is at `y * width + x`.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self._data
+    }
+
+    /// Turn this image into the underlying row-major `Vec` of pixels.
+    pub fn into_vec(self) -> Vec<T> {
+        self._data
+    }
+
+    /// Iterate over each row of pixels, from the top.
+    pub fn rows(&self) -> ::std::slice::Chunks<'_, T> {
+        // chunks(0) panics, but there are no rows anyway when the width is 0
+        self._data.chunks((self._width as usize).max(1))
+    }
+
+    /// Iterate over every pixel value, row-major.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, T> {
+        self._data.iter()
+    }
+
+    /// Iterate mutably over every pixel value, row-major.
+    pub fn iter_mut(&mut self) -> ::std::slice::IterMut<'_, T> {
+        self._data.iter_mut()
+    }
+
+    /// Iterate over every pixel as (`x`, `y`, `value`), row-major.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item=(u32, u32, &T)> + '_ {
+        // When the width is 0, there's no data, so we never divide by it
+        let width = self._width as usize;
+        self._data.iter().enumerate()
+            .map(move |(i, v)| ((i % width) as u32, (i / width) as u32, v))
+    }
+
+    /// Iterate mutably over every pixel as (`x`, `y`, `value`), row-major.
+    pub fn enumerate_pixels_mut(&mut self) -> impl Iterator<Item=(u32, u32, &mut T)> + '_ {
+        let width = self._width as usize;
+        self._data.iter_mut().enumerate()
+            .map(move |(i, v)| ((i % width) as u32, (i / width) as u32, v))
+    }
+
+    /// Iterate over every pixel as (`x`, `y`, `Some((lat, lon))`, `value`), row-major. The
+    /// lat/lon is the same as `pos_for_xy`, so it's `None` for pixels not on the globe.
+    pub fn iter_geo(&self) -> impl Iterator<Item=(u32, u32, Option<(f32, f32)>, &T)> + '_ {
+        self.iter_geo_f64()
+            .map(|(x, y, pos, v)| (x, y, pos.map(|(lat, lon)| (lat as f32, lon as f32)), v))
+    }
+
+    /// Like `iter_geo`, but with the lat/lon as `f64` (i.e. `pos_for_xy_f64`).
+    pub fn iter_geo_f64(&self) -> impl Iterator<Item=(u32, u32, Option<(f64, f64)>, &T)> + '_ {
+        let view = self._view.clone();
+        self.enumerate_pixels()
+            .map(move |(x, y, v)| (x, y, view.pos_for_pixel(x, y), v))
+    }
+
+    /// Iterate mutably over every pixel as (`x`, `y`, `Some((lat, lon))`, `value`), row-major.
+    /// The lat/lon is the same as `pos_for_xy`, so it's `None` for pixels not on the globe.
+    pub fn iter_geo_mut(&mut self) -> impl Iterator<Item=(u32, u32, Option<(f32, f32)>, &mut T)> + '_ {
+        self.iter_geo_mut_f64()
+            .map(|(x, y, pos, v)| (x, y, pos.map(|(lat, lon)| (lat as f32, lon as f32)), v))
+    }
+
+    /// Like `iter_geo_mut`, but with the lat/lon as `f64` (i.e. `pos_for_xy_f64`).
+    pub fn iter_geo_mut_f64(&mut self) -> impl Iterator<Item=(u32, u32, Option<(f64, f64)>, &mut T)> + '_ {
+        let view = self._view.clone();
+        self.enumerate_pixels_mut()
+            .map(move |(x, y, v)| (x, y, view.pos_for_pixel(x, y), v))
+    }
 }
 
 
+#[cfg(test)]
+#[macro_use]
+extern crate proptest;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -138,4 +768,487 @@ mod tests {
         assert_eq!(o.get_pixel(0, 0), &0u8);
 
     }
+
+    #[test]
+    fn test_pos_for_xy() {
+        use super::OrthoProj;
+        let o = OrthoProj::new(100, 0., 0., 0u8);
+        let (lat, lon) = o.pos_for_xy(50, 50).unwrap();
+        assert!(lat.abs() < 1. && lon.abs() < 1.);
+
+        // North is up, east is right
+        let (lat, lon) = o.pos_for_xy(50, 5).unwrap();
+        assert!(lat > 60. && lon.abs() < 2.);
+        let (lat, lon) = o.pos_for_xy(95, 50).unwrap();
+        assert!(lat.abs() < 1. && lon > 60.);
+
+        // Corners are off the globe
+        assert_eq!(o.pos_for_xy(0, 0), None);
+        assert_eq!(o.pos_for_xy(99, 99), None);
+    }
+
+    #[test]
+    fn test_get() {
+        use super::OrthoProj;
+        // Look at the globe from a few places, and check points in every direction from there
+        for &(lat0, lon0) in &[(0., 0.), (45., 90.), (-45., -90.), (30., 179.), (-60., -179.), (89., 10.)] {
+            let mut o = OrthoProj::new(200, lat0, lon0, 0u8);
+            assert_eq!(o.get(lat0, lon0), Some(&0));
+
+            for (i, &(dlat, dlon)) in [(20., 20.), (20., -20.), (-20., 20.), (-20., -20.)].iter().enumerate() {
+                let (lat, lon): (f32, f32) = (lat0 + dlat, lon0 + dlon);
+                let lat = lat.clamp(-90., 90.);
+                let value = i as u8 + 1;
+                o.set(lat, lon, value);
+                assert_eq!(o.get(lat, lon), Some(&value), "centre {:?} point {:?}", (lat0, lon0), (lat, lon));
+                let (x, y) = o.xy_for_pos(lat, lon).unwrap();
+                assert_eq!(o.get_pixel(x, y), &value);
+            }
+
+            // The far side of the globe
+            assert_eq!(o.get(-lat0, lon0 + 180.), None);
+            assert_eq!(o.get_mut(-lat0, lon0 + 180.), None);
+        }
+    }
+
+    #[test]
+    fn test_get_mut() {
+        use super::OrthoProj;
+        let mut o = OrthoProj::new(100, 51.5, 0., 0u32);
+        *o.get_mut(53.3, -6.3).unwrap() += 2;
+        *o.get_mut(53.3, -6.3).unwrap() += 3;
+        assert_eq!(o.get(53.3, -6.3), Some(&5));
+
+        // Right on the eastern edge is past the last pixel
+        let o = OrthoProj::new(100, 0., 0., 0u32);
+        assert_eq!(o.get(0., 90.), None);
+    }
+
+    #[test]
+    fn test_dimensions() {
+        use super::OrthoProj;
+        let o = OrthoProj::new_with_dimensions(1920, 1080, 0., 0., 0u8);
+        assert_eq!((o.width(), o.height(), o.radius()), (1920, 1080, 540.));
+        assert_eq!(o.xy_for_pos(0., 0.), Some((960, 540)));
+        assert_eq!(o.pos_for_xy(100, 540), None);
+        assert_eq!(o.get_pixel(1919, 1079), &0);
+
+        let mut o = OrthoProj::new_with_dimensions(1920, 1080, 0., 0., 0u8)
+            .with_radius(100.)
+            .with_offset(-800., 300.);
+        assert_eq!(o.xy_for_pos(0., 0.), Some((160, 840)));
+        assert_eq!(o.xy_for_pos(0., 89.), Some((259, 840)));
+        assert_eq!(o.xy_for_pos(89., 0.), Some((160, 740)));
+        assert!(o.pos_for_xy(160, 840).is_some());
+        assert!(o.pos_for_xy(960, 540).is_none());
+
+        o.fill_globe(1);
+        assert_eq!(o.get_pixel(160, 840), &1);
+        assert_eq!(o.get_pixel(255, 840), &1);
+        assert_eq!(o.get_pixel(265, 840), &0);
+        assert_eq!(o.get_pixel(960, 540), &0);
+        assert_eq!(o.get(0., 0.), Some(&1));
+
+        // Globe partly off the image
+        let o = OrthoProj::new_with_dimensions(200, 100, 0., 0., 0u8).with_offset(-100., 0.);
+        assert_eq!(o.xy_for_pos(0., -30.), None);
+        assert_eq!(o.xy_for_pos(0., 30.), Some((25, 50)));
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_negative_radius() {
+        use super::OrthoProj;
+        OrthoProj::new(100, 0., 0., 0u8).with_radius(-40.);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_zero_radius() {
+        use super::OrthoProj;
+        OrthoProj::new(100, 0., 0., 0u8).with_radius(0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_nan_radius() {
+        use super::OrthoProj;
+        OrthoProj::new(100, 0., 0., 0u8).with_radius(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_infinite_radius() {
+        use super::OrthoProj;
+        OrthoProj::new(100, 0., 0., 0u8).with_radius(f32::INFINITY);
+    }
+
+    #[test]
+    fn test_disc() {
+        use super::OrthoProj;
+        use std::f64::consts::PI;
+        // Every small square size, & some rectangles, odd & even
+        let sizes = (0..=64).map(|s| (s, s)).chain(vec![(1, 2), (2, 1), (3, 7), (10, 3), (101, 60), (60, 101), (255, 255)]);
+        for (w, h) in sizes {
+            let mut o = OrthoProj::new_with_dimensions(w, h, 10., 20., 0u8);
+            o.fill_globe(1);
+            if w == h {
+                assert_eq!(o.as_slice(), OrthoProj::new_with_bg(w, 10., 20., 0u8, 1).as_slice());
+            }
+
+            for (x, y, &v) in o.enumerate_pixels() {
+                // The same as `pos_for_xy` says is on the globe
+                assert_eq!(v == 1, o.pos_for_xy(x, y).is_some(), "{}x{} ({}, {})", w, h, x, y);
+                // In the middle, so the same both ways around
+                assert_eq!(o.get_pixel(w - 1 - x, y), &v, "{}x{} ({}, {})", w, h, x, y);
+                assert_eq!(o.get_pixel(x, h - 1 - y), &v, "{}x{} ({}, {})", w, h, x, y);
+            }
+
+            let r = w.min(h) as f64 / 2.;
+            let count = o.iter().filter(|&&v| v == 1).count() as f64;
+            assert!((count - PI * r * r).abs() <= 2. * PI * r + 1., "{}x{} {}", w, h, count);
+            if w > 0 && h > 0 {
+                assert_eq!(o.get_pixel(w / 2, h / 2), &1);
+            }
+        }
+
+        let o = OrthoProj::new_with_bg(1, 0., 0., 0u8, 1);
+        assert_eq!(o.as_slice(), &[1]);
+        assert_eq!(o.pos_for_xy(0, 0), Some((0., 0.)));
+        let o = OrthoProj::new_with_bg(0, 0., 0., 0u8, 1);
+        assert!(o.as_slice().is_empty());
+        assert_eq!(o.xy_for_pos(0., 0.), None);
+
+        // Odd sizes have a middle pixel
+        let o = OrthoProj::new(101, 10., 20., 0u8);
+        assert_eq!(o.xy_for_pos(10., 20.), Some((50, 50)));
+        assert_eq!(o.project_f(10., 20.), Some((50.5, 50.5)));
+    }
+
+    #[test]
+    fn test_row_major() {
+        use super::OrthoProj;
+        let mut o = OrthoProj::new_with_dimensions(4, 3, 0., 0., 0u8);
+        o.set_pixel(1, 0, 1);
+        o.set_pixel(3, 2, 2);
+        o.as_mut_slice()[4] = 3;
+        assert_eq!(o.get_pixel(0, 1), &3);
+        assert_eq!(o.as_slice(), &[0, 1, 0, 0, 3, 0, 0, 0, 0, 0, 0, 2]);
+
+        let rows: Vec<&[u8]> = o.rows().collect();
+        assert_eq!(rows, vec![&[0, 1, 0, 0][..], &[3, 0, 0, 0][..], &[0, 0, 0, 2][..]]);
+
+        assert_eq!(o.into_vec().len(), 12);
+
+        let o = OrthoProj::new_with_dimensions(0, 3, 0., 0., 0u8);
+        assert_eq!(o.rows().count(), 0);
+    }
+
+    #[test]
+    fn test_iter() {
+        use super::OrthoProj;
+        let mut o = OrthoProj::new_with_dimensions(3, 2, 0., 0., 0u8);
+        for (i, v) in o.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        assert_eq!(o.iter().cloned().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+
+        let pixels: Vec<_> = o.enumerate_pixels().map(|(x, y, &v)| (x, y, v)).collect();
+        assert_eq!(pixels, vec![(0, 0, 0), (1, 0, 1), (2, 0, 2), (0, 1, 3), (1, 1, 4), (2, 1, 5)]);
+
+        for (x, y, v) in o.enumerate_pixels_mut() {
+            *v = (10*x + y) as u8;
+        }
+        assert_eq!(o.get_pixel(2, 1), &21);
+    }
+
+    #[test]
+    fn test_iter_geo() {
+        use super::OrthoProj;
+        let mut o = OrthoProj::new_with_dimensions(120, 100, 20., 30., 0u8);
+        assert_eq!(o.iter_geo().count(), 120*100);
+        for (x, y, pos, _) in o.iter_geo() {
+            assert_eq!(pos, o.pos_for_xy(x, y));
+        }
+
+        for (_, _, pos, v) in o.iter_geo_mut() {
+            if let Some((lat, _)) = pos {
+                *v = if lat > 0. { 1 } else { 2 };
+            }
+        }
+        assert_eq!(o.get(40., 30.), Some(&1));
+        assert_eq!(o.get(-10., 30.), Some(&2));
+        assert_eq!(o.get_pixel(0, 0), &0);
+
+        // The same, as f64
+        assert_eq!(o.iter_geo_f64().count(), 120*100);
+        for (x, y, pos, _) in o.iter_geo_f64() {
+            assert_eq!(pos, o.pos_for_xy_f64(x, y));
+            assert_eq!(pos.map(|(lat, lon)| (lat as f32, lon as f32)), o.pos_for_xy(x, y));
+        }
+        for (x, y, pos, v) in o.iter_geo_mut_f64() {
+            assert_eq!(pos.is_some(), *v != 0, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn test_f64_16k() {
+        use super::*;
+        // `()` pixels take no memory, so the image can be this large
+        let o = OrthoProj::new(16384, 41.89889f64, 12.47337, ());
+        assert_eq!(o.xy_for_pos(41.89889, 12.47337), Some((8192, 8192)));
+
+        // Points anywhere inside a pixel come back to that pixel, even right by the edge of the
+        // globe, where the map is most squashed
+        let mut checked = 0;
+        for y in (0..16384).step_by(97).chain(8180..8200) {
+            for x in (0..16384).step_by(89).chain(16370..16384) {
+                for &(dx, dy) in &[(0.5, 0.5), (0.01, 0.01), (0.99, 0.5), (0.5, 0.99), (0.99, 0.99)] {
+                    if let Some((lat, lon)) = o._view.pos_for_xy(x as f64 + dx, y as f64 + dy) {
+                        assert_eq!(o.xy_for_pos(lat, lon), Some((x, y)), "({} + {}, {} + {})", x, dx, y, dy);
+                        checked += 1;
+                    }
+                }
+                if let Some((lat, lon)) = o.pos_for_xy_f64(x, y) {
+                    assert_eq!(o.xy_for_pos(lat, lon), Some((x, y)));
+                }
+            }
+        }
+        assert!(checked > 100_000, "{}", checked);
+
+        // f32 & f64 both work, for points & lines
+        let mut o = OrthoProj::new(100, 0f32, 0f32, 0u8);
+        o.set(10f64, 10f64, 1);
+        assert_eq!(o.get(10f32, 10f32), Some(&1));
+        o.draw_great_circle((-10f64, -10f64), (-10., 10.), 2);
+        assert_eq!(o.get(-10f32, 0f32), Some(&2));
+    }
+
+    #[test]
+    fn test_project_f() {
+        use super::OrthoProj;
+        let o = OrthoProj::new_with_dimensions(200, 100, 0., 0., 0u8).with_offset(-100., 0.);
+        assert_eq!(o.project_f(0., 0.), Some((0., 50.)));
+        assert_eq!(o.xy_for_pos(0., 0.), Some((0, 50)));
+        // Off the left of the image, but still on the globe
+        let (x, y) = o.project_f(0., -30.).unwrap();
+        assert!((x + 25.).abs() < 1e-9 && (y - 50.).abs() < 1e-9);
+        assert_eq!(o.xy_for_pos(0., -30.), None);
+        assert_eq!(o.project_f(0., 180.), None);
+        assert_eq!(o.project_f(f64::NAN, 0.), None);
+        assert_eq!(o.xy_for_pos(f64::NAN, 0.), None);
+        // 50 × sin 30° comes out a tiny bit less than 25, but it's still the edge of pixel 25,
+        // for points & lines
+        assert!(o.project_f(0., 30.).unwrap().0 < 25.);
+        assert_eq!(o.xy_for_pos(0., 30.), Some((25, 50)));
+        let mut lines = OrthoProj::new_with_dimensions(200, 100, 0., 0., 0u8).with_offset(-100., 0.);
+        lines.draw_great_circle((0., 30.), (0., 30.), 1);
+        assert_eq!(lines.get_pixel(25, 50), &1);
+        assert_eq!(lines.get_pixel(24, 50), &0);
+
+        // Rounded down to the pixel the point is in
+        let o = OrthoProj::new(100, 0., 0., 0u8);
+        let lon = (0.99f64 / 50.).asin().to_degrees();
+        assert_eq!(o.xy_for_pos(0., lon), Some((50, 50)));
+        assert_eq!(o.xy_for_pos(0., -lon), Some((49, 50)));
+        // Right on the edge of the image is off it
+        assert_eq!(o.project_f(0., 90.), Some((100., 50.)));
+        assert_eq!(o.xy_for_pos(0., 90.), None);
+
+        // The centre of each pixel goes back to the centre
+        for (x, y, pos, _) in o.iter_geo() {
+            if pos.is_some() {
+                let (lat, lon) = o.pos_for_xy_f64(x, y).unwrap();
+                let (x2, y2) = o.project_f(lat, lon).unwrap();
+                assert!((x2 - x as f64 - 0.5).abs() < 1e-9 && (y2 - y as f64 - 0.5).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        use super::OrthoProj;
+        let o = OrthoProj::new(100, 20., 30., 0u8);
+        assert_eq!(o.with_rotation(0.).xy_for_pos(40., 50.), OrthoProj::new(100, 20., 30., 0u8).xy_for_pos(40., 50.));
+
+        // North is on the left, east is up
+        let o = OrthoProj::new(100, 0., 0., 0u8).with_rotation(90.);
+        let (x, y) = o.xy_for_pos(30., 0.).unwrap();
+        assert!(x < 30 && y == 50);
+        let (x, y) = o.xy_for_pos(0., 30.).unwrap();
+        assert!(x == 50 && y < 30);
+        let (lat, lon) = o.pos_for_xy(50, 5).unwrap();
+        assert!(lat.abs() < 1. && lon > 60.);
+
+        // Upside down is the same as going through the centre
+        let o = OrthoProj::new(100, 20., 30., 0u8);
+        let upside_down = OrthoProj::new(100, 20., 30., 0u8).with_rotation(180.);
+        let (x, y) = o.project_f(40., 50.).unwrap();
+        let (x2, y2) = upside_down.project_f(40., 50.).unwrap();
+        assert!((x + x2 - 100.).abs() < 1e-9 && (y + y2 - 100.).abs() < 1e-9);
+        assert_eq!(o.project_f(20., 30.), upside_down.project_f(20., 30.));
+    }
+
+    #[test]
+    fn test_projections() {
+        use super::*;
+        use std::f64::consts::PI;
+
+        /// Points go to the right place, and the map takes up `fraction` of the rectangle around
+        /// it
+        fn check<P: Projection + PartialEq + ::std::fmt::Debug>(proj: P, fraction: f64) {
+            let mut o = OrthoProj::new_with_projection(200, 200, proj.clone(), 0u8);
+            let (lat, lon) = proj.centre();
+            assert_eq!(o.xy_for_pos(lat as f32, lon as f32), Some((100, 100)));
+            o.set(lat as f32 + 5., lon as f32, 1);
+            assert_eq!(o.get(lat as f32 + 5., lon as f32), Some(&1));
+            assert_eq!(o.projection(), &proj);
+
+            for (x, y, pos, _) in o.iter_geo() {
+                if let Some((lat, lon)) = pos {
+                    let (x2, y2) = o.xy_for_pos(lat, lon).unwrap();
+                    // The left & right edges are the same place on world maps
+                    let dx = (x as i64 - x2 as i64).abs();
+                    assert!((dx <= 1 || dx >= 197) && (y as i64 - y2 as i64).abs() <= 1, "({}, {}) -> {} {} -> ({}, {})", x, y, lat, lon, x2, y2);
+                }
+            }
+
+            // The map just fits
+            let (extent_x, extent_y) = proj.extent();
+            let (width, height) = (2. * extent_x * o.radius() as f64, 2. * extent_y * o.radius() as f64);
+            assert!((width.max(height) - 200.).abs() < 1.);
+            o.fill_globe(2);
+            let count = o.iter().filter(|&&v| v == 2).count() as f64;
+            let area = fraction * width * height;
+            assert!((count - area).abs() < area * 0.02, "{} {}", count, area);
+        }
+
+        // Azimuthal maps are a disc
+        let disc = PI / 4.;
+        check(Orthographic::new(10., 20.), disc);
+        check(Orthographic::new(10., 20.).with_ellipsoid(Ellipsoid::WGS84), disc);
+        check(Gnomonic::new(10., 20.), disc);
+        check(Stereographic::new(-80., 20.).with_max_angle(140.), disc);
+        check(LambertAzimuthalEqualArea::new(90., 0.), disc);
+        check(AzimuthalEquidistant::new(-45., 170.), disc);
+        check(Perspective::new(45., -120., 10000.), disc);
+
+        check(PlateCarree::new(0.), 1.);
+        check(WebMercator::new(-90.), 1.);
+        check(Mollweide::new(170.), PI / 4.);
+        check(Robinson::new(0.), 0.8726);
+        check(WinkelTripel::new(0.), 0.8234);
+
+        // Anything can be seen on a whole world map
+        let o = OrthoProj::new_with_projection(200, 200, AzimuthalEquidistant::new(51.5, 0.), 0u8);
+        assert!(o.xy_for_pos(-51.4, 179.9).is_some());
+        assert!(o.xy_for_pos(-51.4, -179.9).is_some());
+    }
+
+    #[test]
+    fn test_from_fn() {
+        use super::OrthoProj;
+        let o = OrthoProj::from_fn(200, 0., 0., None, |lat, lon| Some((lat, lon)));
+        for (x, y, pos, value) in o.iter_geo() {
+            assert_eq!(value, &pos, "pixel ({}, {})", x, y);
+        }
+        assert!(o.get_pixel(0, 0).is_none());
+        assert!(o.get_pixel(100, 100).is_some());
+
+        // Only the globe is changed
+        let mut o = OrthoProj::new_with_bg(100, 10., 10., 0u8, 1);
+        o.fill_with(|lat, _| if lat < 10. { 2 } else { 3 });
+        assert_eq!(o.get_pixel(0, 0), &0);
+        assert_eq!(o.get(0., 10.), Some(&2));
+        assert_eq!(o.get(20., 10.), Some(&3));
+        assert!(o.iter().all(|&v| v != 1));
+
+        // As f64, which is precise enough for very large images
+        let o = OrthoProj::from_fn_f64(200, 0., 0., None, |lat, lon| Some((lat, lon)));
+        for (x, y, pos, value) in o.iter_geo_f64() {
+            assert_eq!(value, &pos, "pixel ({}, {})", x, y);
+        }
+        let mut o = OrthoProj::new(100, 10., 10., (0., 0.));
+        o.fill_with_f64(|lat, lon| (lat, lon));
+        for (x, y, &(lat, lon)) in o.enumerate_pixels() {
+            assert_eq!(o.pos_for_xy_f64(x, y).unwrap_or((0., 0.)), (lat, lon));
+        }
+        assert_eq!(o.radius_f64(), 50.);
+    }
+
+    proptest! {
+        #[test]
+        fn pos_for_xy_round_trips(width in 2u32..2000, height in 2u32..2000, radius in 1f32..1000., dx in -500f32..500., dy in -500f32..500., lat0 in -90f32..90., lon0 in -180f32..180., x in 0f32..1., y in 0f32..1.) {
+            use super::OrthoProj;
+            let o = OrthoProj::new_with_dimensions(width, height, lat0, lon0, 0u8)
+                .with_radius(radius)
+                .with_offset(dx, dy);
+            let x = (x * width as f32) as u32;
+            let y = (y * height as f32) as u32;
+
+            if let Some((lat, lon)) = o.pos_for_xy(x, y) {
+                prop_assert!((-90. ..=90.).contains(&lat));
+                prop_assert!((-180. ..=180.).contains(&lon));
+
+                let (x2, y2) = o.xy_for_pos(lat, lon).unwrap();
+                prop_assert!((x as i64 - x2 as i64).abs() <= 1, "x {} -> {}", x, x2);
+                prop_assert!((y as i64 - y2 as i64).abs() <= 1, "y {} -> {}", y, y2);
+            }
+        }
+
+        #[test]
+        fn pos_for_xy_f64_round_trips(size in 2u32..16384, lat0 in -90f64..90., lon0 in -180f64..180., x in 0f64..1., y in 0f64..1.) {
+            use super::OrthoProj;
+            let o = OrthoProj::new(size, lat0, lon0, ());
+            let x = ((x * size as f64) as u32).min(size - 1);
+            let y = ((y * size as f64) as u32).min(size - 1);
+
+            if let Some((lat, lon)) = o.pos_for_xy_f64(x, y) {
+                prop_assert_eq!(o.xy_for_pos(lat, lon), Some((x, y)));
+            }
+        }
+
+        #[test]
+        fn xy_for_pos_is_in_bounds(width in 0u32..500, height in 0u32..500, radius in 0.001f64..1000., dx in -500f64..500., dy in -500f64..500., lat0 in -90f64..90., lon0 in -180f64..180., lat in -90f64..90., lon in -180f64..180.) {
+            use super::OrthoProj;
+            let o = OrthoProj::new_with_dimensions(width, height, lat0, lon0, 0u8)
+                .with_radius(radius)
+                .with_offset(dx, dy);
+
+            if let Some((x, y)) = o.xy_for_pos(lat, lon) {
+                prop_assert!(x < width && y < height);
+                let (xf, yf) = o.project_f(lat, lon).unwrap();
+                prop_assert_eq!((x, y), (super::pixel_floor(xf) as u32, super::pixel_floor(yf) as u32));
+            }
+        }
+
+        #[test]
+        fn rotated_round_trips(size in 2u32..2000, lat0 in -90f64..90., lon0 in -180f64..180., rotation in -360f64..360., x in 0f64..1., y in 0f64..1.) {
+            use super::OrthoProj;
+            let o = OrthoProj::new(size, lat0, lon0, ()).with_rotation(rotation);
+            let x = ((x * size as f64) as u32).min(size - 1);
+            let y = ((y * size as f64) as u32).min(size - 1);
+
+            if let Some((lat, lon)) = o.pos_for_xy_f64(x, y) {
+                prop_assert_eq!(o.xy_for_pos(lat, lon), Some((x, y)));
+            }
+        }
+
+        #[test]
+        fn xy_for_pos_round_trips(size in 2u32..2000, lat0 in -90f32..90., lon0 in -180f32..180., lat in -90f32..90., lon in -180f32..180.) {
+            use super::OrthoProj;
+            let o = OrthoProj::new(size, lat0, lon0, 0u8);
+
+            if let Some((x, y)) = o.xy_for_pos(lat, lon) {
+                if x < size && y < size {
+                    // Pixels right on the edge might have their centre off the globe
+                    if let Some((lat2, lon2)) = o.pos_for_xy(x, y) {
+                        let (x2, y2) = o.xy_for_pos(lat2, lon2).unwrap();
+                        prop_assert!((x as i64 - x2 as i64).abs() <= 1, "x {} -> {}", x, x2);
+                        prop_assert!((y as i64 - y2 as i64).abs() <= 1, "y {} -> {}", y, y2);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,236 @@
+//! Drawing points: circles a size on the globe, & markers a size on the image
+
+use std::f64::consts::PI;
+
+use {Float, OrthoProj, Projection, EARTH_RADIUS_KM};
+use sphere;
+
+/// The shape of a marker drawn with `OrthoProj::draw_marker`. All of them are filled, and fit in
+/// a circle of the marker's radius.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+    /// A disc
+    Circle,
+    /// A square, with its sides up & down, & left & right
+    Square,
+    /// A square on its corner
+    Diamond,
+    /// A +, 1 pixel wide
+    Plus,
+    /// An ×, 1 pixel wide
+    Cross,
+}
+
+impl Marker {
+    /// Is the point `dx`, `dy` pixels from the middle of a marker `radius` pixels big part of it
+    fn contains(&self, dx: f64, dy: f64, radius: f64) -> bool {
+        let (dx, dy) = (dx.abs(), dy.abs());
+        let in_circle = dx*dx + dy*dy <= radius*radius;
+        match *self {
+            Marker::Circle => in_circle,
+            Marker::Square => dx.max(dy) <= radius / 2f64.sqrt(),
+            Marker::Diamond => dx + dy <= radius,
+            Marker::Plus => in_circle && dx.min(dy) <= 0.5,
+            // How far it is from the nearest diagonal
+            Marker::Cross => in_circle && (dx - dy).abs() / 2f64.sqrt() <= 0.5,
+        }
+    }
+}
+
+impl<T: Clone, P: Projection> OrthoProj<T, P> {
+    /// Set every pixel within `radius_km` km of `lat`, `lon` on the Earth to `value`. This is a
+    /// circle on the globe, so it's squashed like everything else towards the edge, and cut off
+    /// where it goes around the back. The pixel `lat`, `lon` is in is always set, so small
+    /// circles don't disappear.
+    ///
+    ///```
+    ///# use orthoproj::OrthoProj;
+    ///let mut image = OrthoProj::new(500, 41.89889, 12.47337, 0u8);
+    ///// Within 100km of Rome
+    ///image.draw_circle(41.89889, 12.47337, 100., 1);
+    ///assert_eq!(image.get(41.89889, 12.97337), Some(&1));
+    ///assert_eq!(image.get(41.89889, 13.97337), Some(&0));
+    ///```
+    pub fn draw_circle<F: Float>(&mut self, lat: F, lon: F, radius_km: F, value: T) {
+        let (lat, lon) = (lat.to_f64(), lon.to_f64());
+        let angle = radius_km.to_f64() / EARTH_RADIUS_KM;
+        let centre = sphere::from_pos(lat, lon);
+        if angle >= PI / 2. - 1e-6 {
+            // The polygon would be the bigger part of the globe, which `fill_polygon` doesn't do,
+            // so look at every pixel. This isn't very common. Around a hemisphere, both sides are
+            // about the same size, & `fill_polygon` can't tell which is smaller, so do this then
+            // too.
+            let min_dot = angle.min(PI).cos();
+            let view = self._view.clone();
+            for (x, y, v) in self.enumerate_pixels_mut() {
+                if let Some((lat, lon)) = view.pos_for_xy(x as f64 + 0.5, y as f64 + 0.5) {
+                    if sphere::dot(sphere::from_pos(lat, lon), centre) >= min_dot {
+                        *v = value.clone();
+                    }
+                }
+            }
+        } else if angle > 0. {
+            let ring: Vec<_> = sphere::small_circle(centre, angle).into_iter().map(sphere::to_pos).collect();
+            self.fill_polygon(&ring, value.clone());
+        }
+        self.set(lat, lon, value);
+    }
+
+    /// Draw a `shape` marker, `radius` pixels big, in the middle of `lat`, `lon`, setting those
+    /// pixels to `value`. It's the same size & shape anywhere on the image, so it's not squashed
+    /// at the edge like `draw_circle`. Nothing is drawn if `lat`, `lon` isn't on the globe, but
+    /// the marker can go off the edge of the globe. The pixel `lat`, `lon` is in is always set.
+    ///
+    ///```
+    ///use orthoproj::{Marker, OrthoProj};
+    ///let mut image = OrthoProj::new(500, 41.89889, 12.47337, 0u8);
+    ///image.draw_marker(51.50791, -0.12786, 3., Marker::Diamond, 1);
+    ///let (x, y) = image.xy_for_pos(51.50791, -0.12786).unwrap();
+    ///assert_eq!(image.get_pixel(x + 2, y), &1);
+    ///assert_eq!(image.get_pixel(x + 4, y), &0);
+    ///```
+    pub fn draw_marker<F: Float>(&mut self, lat: F, lon: F, radius: F, shape: Marker, value: T) {
+        let (x, y) = match self.project_f(lat, lon) {
+            Some(xy) => xy,
+            None => return,
+        };
+        let radius = radius.to_f64().max(0.);
+        let (x0, x1) = ((x - radius).floor().max(0.), (x + radius).ceil().min(self._width as f64));
+        let (y0, y1) = ((y - radius).floor().max(0.), (y + radius).ceil().min(self._height as f64));
+        for py in y0 as u32..y1.max(y0) as u32 {
+            for px in x0 as u32..x1.max(x0) as u32 {
+                if shape.contains(px as f64 + 0.5 - x, py as f64 + 0.5 - y, radius) {
+                    self.set_pixel(px, py, value.clone());
+                }
+            }
+        }
+        self.set(lat, lon, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Stereographic;
+    use test_helpers::count;
+
+    #[test]
+    fn test_draw_circle() {
+        // 1000km is about 0.157 radians, so about 15.7 pixels on a globe with a radius of 100
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 0., 1000., 1);
+        let r = 100. * (1000. / EARTH_RADIUS_KM).sin();
+        let area = PI * r * r;
+        assert!((count(&o, 1) as f64 - area).abs() < 2. * PI * r, "{} {}", count(&o, 1), area);
+        assert_eq!(o.get(8.9, 0.), Some(&1));
+        assert_eq!(o.get(0., -8.9), Some(&1));
+        assert_eq!(o.get(10., 0.), Some(&0));
+
+        // Squashed near the edge, so it's narrower than it is tall
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 70., 1000., 1);
+        let (xs, ys): (Vec<u32>, Vec<u32>) = o.enumerate_pixels().filter(|p| *p.2 == 1).map(|(x, y, _)| (x, y)).unzip();
+        let width = xs.iter().max().unwrap() - xs.iter().min().unwrap() + 1;
+        let height = ys.iter().max().unwrap() - ys.iter().min().unwrap() + 1;
+        assert!(width * 2 < height, "{} {}", width, height);
+
+        // Cut off at the edge
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 90., 1000., 1);
+        assert!(count(&o, 1) > 10 && count(&o, 1) < 100, "{}", count(&o, 1));
+        assert_eq!(o.get(0., 85.), Some(&1));
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 180., 1000., 1);
+        assert_eq!(count(&o, 1), 0);
+
+        // Tiny circles are still there
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(10., 10., 0.001, 1);
+        assert_eq!(count(&o, 1), 1);
+        assert_eq!(o.get(10., 10.), Some(&1));
+
+        // More than a quarter of the way around, seen from the other side
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 180., 15000., 1);
+        let r = 100. * (PI - 15000. / EARTH_RADIUS_KM).sin();
+        let mut all = OrthoProj::new_with_bg(200, 0., 0., 0u8, 1);
+        assert!((count(&o, 1) as f64 - (count(&all, 1) as f64 - PI * r * r)).abs() < 2. * PI * r);
+        assert_eq!(o.get(0., 0.), Some(&0));
+        assert_eq!(o.get(0., 80.), Some(&1));
+        // & all the way around
+        all.fill_globe(0);
+        all.draw_circle(0., 180., 30000., 1);
+        assert_eq!(all.as_slice(), OrthoProj::new_with_bg(200, 0., 0., 0u8, 1).as_slice());
+
+        // About a hemisphere, where both sides of the ring are about the same size
+        for &(lat, lon, scale) in &[(0., 0., 1.), (0., 0., 1. - 1e-9), (30., 40., 1. - 1e-9), (-30., 100., 1. - 1e-9)] {
+            let radius_km = EARTH_RADIUS_KM * PI / 2. * scale;
+            let mut o = OrthoProj::new(200, 0., 0., 0u8);
+            o.draw_circle(lat, lon, radius_km, 1);
+            let centre = sphere::from_pos(lat, lon);
+            let expected = (0..200).flat_map(|y| (0..200).map(move |x| (x, y)))
+                .filter_map(|(x, y)| o.pos_for_xy_f64(x, y))
+                .filter(|&(lat, lon)| sphere::angle(sphere::from_pos(lat, lon), centre) <= PI / 2.)
+                .count();
+            assert!((count(&o, 1) as f64 - expected as f64).abs() <= 200., "{} {} {} {}", lat, lon, count(&o, 1), expected);
+        }
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_circle(0., 0., EARTH_RADIUS_KM * PI / 2., 1);
+        assert!(count(&o, 1) > 31000, "{}", count(&o, 1));
+
+        // On other projections
+        let mut o = OrthoProj::new_with_projection(200, 200, Stereographic::new(90., 0.), 0u8);
+        o.draw_circle(90., 0., 2000., 1);
+        assert_eq!(o.get(75., 45.), Some(&1));
+        assert_eq!(o.get(70., 45.), Some(&0));
+    }
+
+    #[test]
+    fn test_draw_marker() {
+        let shapes = [Marker::Circle, Marker::Square, Marker::Diamond, Marker::Plus, Marker::Cross];
+        let mut sizes = Vec::new();
+        for &shape in &shapes {
+            let mut o = OrthoProj::new(200, 0., 0., 0u8);
+            o.draw_marker(0., 0., 5., shape, 1);
+            // The same shape every way around, in a 10x10 box in the middle
+            for (x, y, &v) in o.enumerate_pixels() {
+                assert_eq!(o.get_pixel(199 - x, y), &v);
+                assert_eq!(o.get_pixel(y, x), &v);
+                if v == 1 {
+                    assert!((95..105).contains(&x) && (95..105).contains(&y), "{:?} ({}, {})", shape, x, y);
+                }
+            }
+            sizes.push(count(&o, 1));
+
+            // The same size & shape at the edge, for a point in the same place in its pixel
+            let mut o = OrthoProj::new(200, 0., 0., 0u8);
+            let (lat, lon) = o.pos_for_xy_f64(186, 100).unwrap();
+            o.draw_marker(lat, lon, 5.5, shape, 1);
+            let mut centred = OrthoProj::new(200, 0., 0., 0u8);
+            let (lat, lon) = centred.pos_for_xy_f64(100, 100).unwrap();
+            centred.draw_marker(lat, lon, 5.5, shape, 1);
+            assert_eq!(count(&o, 1), count(&centred, 1), "{:?}", shape);
+            for (x, y, &v) in centred.enumerate_pixels().filter(|p| p.0 < 114) {
+                assert_eq!(o.get_pixel(x + 86, y), &v, "{:?} ({}, {})", shape, x, y);
+            }
+        }
+        // Circle, square, diamond, plus, cross
+        assert_eq!(sizes, vec![80, 64, 60, 36, 16]);
+
+        // Not on the globe
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_marker(0., 180., 5., Marker::Circle, 1);
+        assert_eq!(count(&o, 1), 0);
+        // Over the edge of the image
+        let o = OrthoProj::new(100, 0., 0., 0u8);
+        let mut o = o.with_radius(60.);
+        o.draw_marker(0., 55., 10., Marker::Square, 1);
+        assert!(count(&o, 1) > 10);
+        assert_eq!(o.get_pixel(99, 50), &1);
+
+        // Tiny markers are still there
+        let mut o = OrthoProj::new(200, 0., 0., 0u8);
+        o.draw_marker(10., 10., 0., Marker::Circle, 1);
+        assert_eq!(count(&o, 1), 1);
+    }
+}
@@ -0,0 +1,46 @@
+//! Pixel values which can be saved straight to image files
+
+/// A pixel value which is made of 8 bit channels, so it can be saved in an image file.
+///
+/// This is implemented for `u8` (greyscale), `[u8; 2]` (greyscale & alpha), `[u8; 3]` (RGB) and
+/// `[u8; 4]` (RGBA). Other pixel types can be turned into one of these when saving, with methods
+/// like `OrthoProj::write_pam_with`.
+pub trait Pixel {
+    /// How many channels (bytes) are in this pixel: 1 grey, 2 grey & alpha, 3 RGB, 4 RGBA.
+    const CHANNELS: u8;
+
+    /// Add the bytes for this pixel to `bytes`
+    fn extend_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+impl Pixel for u8 {
+    const CHANNELS: u8 = 1;
+
+    fn extend_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self);
+    }
+}
+
+impl Pixel for [u8; 2] {
+    const CHANNELS: u8 = 2;
+
+    fn extend_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self);
+    }
+}
+
+impl Pixel for [u8; 3] {
+    const CHANNELS: u8 = 3;
+
+    fn extend_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self);
+    }
+}
+
+impl Pixel for [u8; 4] {
+    const CHANNELS: u8 = 4;
+
+    fn extend_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self);
+    }
+}
@@ -0,0 +1,107 @@
+//! Saving `OrthoProj` images as PNG files, with the `png` feature
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use png;
+
+use {OrthoProj, Pixel, Projection};
+
+impl<T: Clone + Pixel, Proj: Projection> OrthoProj<T, Proj> {
+    /// Save this image as a PNG file at `path`.
+    ///
+    ///```no_run
+    ///# use orthoproj::OrthoProj;
+    ///let image = OrthoProj::new_with_bg(500, 41.89889, 12.47337, [0u8, 0, 0], [0, 0, 255]);
+    ///image.save_png("rome.png").unwrap();
+    ///```
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.save_png_with(path, |v| v.clone())
+    }
+}
+
+impl<T: Clone, Proj: Projection> OrthoProj<T, Proj> {
+    /// Save this image as a PNG file at `path`, using `colour` to turn each pixel value into a
+    /// `Pixel`, like greyscale `u8`, or RGB `[u8; 3]`.
+    ///
+    ///```no_run
+    ///# use orthoproj::OrthoProj;
+    ///let image = OrthoProj::new_with_bg(500, 41.89889, 12.47337, false, true);
+    ///image.save_png_with("rome.png", |&on_globe| if on_globe { [0u8, 0, 255] } else { [0, 0, 0] }).unwrap();
+    ///```
+    pub fn save_png_with<P, C, F>(&self, path: P, colour: F) -> io::Result<()>
+        where P: AsRef<Path>, C: Pixel, F: Fn(&T) -> C
+    {
+        let mut bytes = Vec::new();
+        for value in self.iter() {
+            colour(value).extend_bytes(&mut bytes);
+        }
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(match C::CHANNELS {
+            1 => png::ColorType::Grayscale,
+            2 => png::ColorType::GrayscaleAlpha,
+            3 => png::ColorType::Rgb,
+            _ => png::ColorType::Rgba,
+        });
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&bytes)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+
+    use png;
+
+    use OrthoProj;
+
+    /// Read the PNG file back, returning the info & the bytes
+    fn read_png(path: &::std::path::Path) -> (png::OutputInfo, Vec<u8>) {
+        let decoder = png::Decoder::new(::std::io::BufReader::new(File::open(path).unwrap()));
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        buf.truncate(info.buffer_size());
+        (info, buf)
+    }
+
+    #[test]
+    fn test_save_png() {
+        let dir = env::temp_dir();
+
+        let mut o = OrthoProj::new_with_dimensions(4, 3, 0., 0., 0u8);
+        o.set_pixel(1, 0, 200);
+        let path = dir.join("orthoproj_test_grey.png");
+        o.save_png(&path).unwrap();
+        let (info, bytes) = read_png(&path);
+        assert_eq!((info.width, info.height, info.color_type), (4, 3, png::ColorType::Grayscale));
+        assert_eq!(bytes, o.as_slice());
+        fs::remove_file(&path).unwrap();
+
+        let o = OrthoProj::new_with_bg(10, 0., 0., [1u8, 2, 3], [4, 5, 6]);
+        let path = dir.join("orthoproj_test_rgb.png");
+        o.save_png(&path).unwrap();
+        let (info, bytes) = read_png(&path);
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(&bytes[..3], &[1, 2, 3]);
+        assert_eq!(&bytes[(5*10 + 5)*3..][..3], &[4, 5, 6]);
+        fs::remove_file(&path).unwrap();
+
+        let o = OrthoProj::new_with_bg(10, 0., 0., 0.5f32, 1.);
+        let path = dir.join("orthoproj_test_rgba.png");
+        o.save_png_with(&path, |&v| [0u8, 0, 0, (v * 255.) as u8]).unwrap();
+        let (info, bytes) = read_png(&path);
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(&bytes[..4], &[0, 0, 0, 127]);
+        assert_eq!(&bytes[(5*10 + 5)*4..][..4], &[0, 0, 0, 255]);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+//! Writing `OrthoProj` images as PGM, PPM & PAM (netpbm) files. These are simple enough to not
+//! need any other library, and tools like ffmpeg can read a stream of them.
+
+use std::io::{self, Write};
+
+use {OrthoProj, Pixel, Projection};
+
+impl<P: Projection> OrthoProj<u8, P> {
+    /// Write this image, as a binary greyscale PGM (`P5`) file, to `w`.
+    pub fn write_pgm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P5\n{} {}\n255\n", self.width(), self.height())?;
+        self.write_pixels(w, |v| *v)
+    }
+}
+
+impl<P: Projection> OrthoProj<[u8; 3], P> {
+    /// Write this image, as a binary RGB PPM (`P6`) file, to `w`.
+    ///
+    /// This sends a 30 frame video of the globe turning to ffmpeg:
+    ///
+    ///```no_run
+    ///# use orthoproj::OrthoProj;
+    ///use std::process::{Command, Stdio};
+    ///let mut ffmpeg = Command::new("ffmpeg")
+    ///    .args(&["-f", "image2pipe", "-c:v", "ppm", "-i", "-", "globe.mp4"])
+    ///    .stdin(Stdio::piped())
+    ///    .spawn().unwrap();
+    ///let mut stdin = ffmpeg.stdin.take().unwrap();
+    ///for frame in 0..30 {
+    ///    let image = OrthoProj::from_fn(500, 0., frame as f32 * 12., [0, 0, 0], |lat, lon| {
+    ///        [(lat + 90.) as u8, ((lon + 180.) / 2.) as u8, 255]
+    ///    });
+    ///    image.write_ppm(&mut stdin).unwrap();
+    ///}
+    ///drop(stdin);
+    ///ffmpeg.wait().unwrap();
+    ///```
+    pub fn write_ppm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        self.write_pixels(w, |v| *v)
+    }
+}
+
+impl<T: Clone + Pixel, P: Projection> OrthoProj<T, P> {
+    /// Write this image, as a PAM (`P7`) file, to `w`. This can store greyscale, greyscale &
+    /// alpha, RGB or RGBA pixels.
+    pub fn write_pam<W: Write>(&self, w: W) -> io::Result<()> {
+        self.write_pam_with(w, |v| v.clone())
+    }
+}
+
+impl<T: Clone, P: Projection> OrthoProj<T, P> {
+    /// Write this image, as a PAM (`P7`) file, to `w`, using `colour` to turn each pixel value
+    /// into a `Pixel`, like greyscale `u8`, or RGB `[u8; 3]`.
+    pub fn write_pam_with<W, C, F>(&self, mut w: W, colour: F) -> io::Result<()>
+        where W: Write, C: Pixel, F: Fn(&T) -> C
+    {
+        let tupltype = match C::CHANNELS {
+            1 => "GRAYSCALE",
+            2 => "GRAYSCALE_ALPHA",
+            3 => "RGB",
+            _ => "RGB_ALPHA",
+        };
+        write!(w, "P7\nWIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL 255\nTUPLTYPE {}\nENDHDR\n",
+               self.width(), self.height(), C::CHANNELS, tupltype)?;
+        self.write_pixels(w, colour)
+    }
+
+    /// Write the bytes of every pixel, after turning them into a `Pixel` with `colour`, one row at
+    /// a time.
+    fn write_pixels<W, C, F>(&self, mut w: W, colour: F) -> io::Result<()>
+        where W: Write, C: Pixel, F: Fn(&T) -> C
+    {
+        let mut bytes = Vec::with_capacity(self.width() as usize * C::CHANNELS as usize);
+        for row in self.rows() {
+            bytes.clear();
+            for value in row {
+                colour(value).extend_bytes(&mut bytes);
+            }
+            w.write_all(&bytes)?;
+        }
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use OrthoProj;
+
+    #[test]
+    fn test_write_pgm() {
+        let mut o = OrthoProj::new_with_dimensions(3, 2, 0., 0., 0u8);
+        o.set_pixel(1, 0, 200);
+        o.set_pixel(2, 1, 10);
+        let mut out = Vec::new();
+        o.write_pgm(&mut out).unwrap();
+        assert_eq!(out, b"P5\n3 2\n255\n\x00\xc8\x00\x00\x00\x0a");
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut o = OrthoProj::new_with_dimensions(2, 1, 0., 0., [0u8, 0, 0]);
+        o.set_pixel(1, 0, [1, 2, 3]);
+        let mut out = Vec::new();
+        o.write_ppm(&mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\x00\x00\x00\x01\x02\x03");
+    }
+
+    #[test]
+    fn test_write_pam() {
+        let o = OrthoProj::new_with_dimensions(1, 2, 0., 0., [1u8, 2, 3, 4]);
+        let mut out = Vec::new();
+        o.write_pam(&mut out).unwrap();
+        assert_eq!(out, &b"P7\nWIDTH 1\nHEIGHT 2\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n\x01\x02\x03\x04\x01\x02\x03\x04"[..]);
+
+        let o = OrthoProj::new_with_dimensions(2, 1, 0., 0., true);
+        let mut out = Vec::new();
+        o.write_pam_with(&mut out, |&v| [v as u8 * 255, 128]).unwrap();
+        assert_eq!(out, &b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 2\nMAXVAL 255\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n\xff\x80\xff\x80"[..]);
+    }
+}
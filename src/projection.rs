@@ -0,0 +1,728 @@
+//! The projection maths, separate from any image
+//!
+//! Every projection here is azimuthal: it's centred on a lat/lon, and points the same angle from
+//! the centre are the same distance from the middle of the map. Maps of the whole world are in
+//! `world`.
+
+use std::f64::consts::PI;
+
+use {Ellipsoid, Float, EARTH_RADIUS_KM};
+
+/// A map projection, from lat/lon to points on a map of a globe with a radius of 1, & back.
+///
+/// The centre of the map is (0, 0), with `x` going east and `y` going north. `OrthoProj` can use
+/// any projection, and scales the map up to the image.
+pub trait Projection: Clone {
+    /// Where `lat`/`lon` (in degrees) is on the map. `None` if it's not shown, i.e. it's outside
+    /// `boundary()`.
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)>;
+
+    /// What lat/lon (in degrees) is at `x`, `y` on the map. `None` if it's off the map. `lon` is
+    /// in -180..180.
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)>;
+
+    /// The lat/lon (in degrees) at the centre of the map.
+    fn centre(&self) -> (f64, f64);
+
+    /// Which part of the globe the map shows, so lines & polygons can be cut off at its edge.
+    fn boundary(&self) -> Boundary;
+
+    /// How far the map goes from the centre, east/west and north/south, so it can be fit on an
+    /// image.
+    fn extent(&self) -> (f64, f64);
+}
+
+/// Which part of the globe a map shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// Every point up to this angle (in degrees) from the centre, like azimuthal projections. The
+    /// edge of the map is a circle.
+    Circle(f64),
+    /// The whole world, from this latitude (in degrees) south up to this latitude north, cut
+    /// along the meridian opposite the centre, like most world maps. The edge of the map is the
+    /// cut on the left & right, and the top & bottom latitudes.
+    Antimeridian(f64),
+}
+
+/// The centre of an azimuthal projection, which does the maths they all share.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Centre {
+    /// In radians
+    lat: f64,
+    lon: f64,
+}
+
+impl Centre {
+    fn new(lat: f64, lon: f64) -> Self {
+        Centre{ lat: lat.to_radians(), lon: lon.to_radians() }
+    }
+
+    fn degrees(&self) -> (f64, f64) {
+        (self.lat.to_degrees(), self.lon.to_degrees())
+    }
+
+    /// For `lat`/`lon` (in degrees), cos(c), where c is the angle from the centre, and where it is
+    /// on the orthographic projection. All the azimuthal projections are this, scaled up by an
+    /// amount which depends on c.
+    fn forward(&self, lat: f64, lon: f64) -> (f64, f64, f64) {
+        // lat = phi
+        // lon = lambda
+        //
+        // x = cos(lat)sin(lon - lon0)
+        // y = cos(lat0)sin(lat) - sin(lat0)cos(lat)cos(lon-lon0)
+        //
+        // cos c = sin(lat0)sin(lat) + cos(lat0)cos(lat)cos(lon-lon0)
+        let lat = lat.to_radians();
+        let lon = lon.to_radians();
+        let cos_c = self.lat.sin() * lat.sin() + self.lat.cos()*lat.cos()*(lon - self.lon).cos();
+        let x = lat.cos() * (lon - self.lon).sin();
+        let y = self.lat.cos()*lat.sin() - self.lat.sin()*lat.cos()*(lon - self.lon).cos();
+        (cos_c, x, y)
+    }
+
+    /// The lat/lon (in degrees) which is `c` radians from the centre, in the direction of `x`,
+    /// `y`, which are `rho` from (0, 0).
+    fn inverse(&self, x: f64, y: f64, rho: f64, c: f64) -> (f64, f64) {
+        // lat = asin( cos(c)sin(lat0) + y sin(c)cos(lat0)/rho )
+        // lon = lon0 + atan2( x sin(c), rho cos(c)cos(lat0) - y sin(c)sin(lat0) )
+        if rho == 0. {
+            return self.degrees();
+        }
+        let (sin_c, cos_c) = c.sin_cos();
+
+        let lat = (cos_c*self.lat.sin() + y*sin_c*self.lat.cos()/rho).asin();
+        let lon = self.lon + (x*sin_c).atan2(rho*cos_c*self.lat.cos() - y*sin_c*self.lat.sin());
+
+        // Keep lon in -180..180
+        let mut lon = lon.to_degrees();
+        if lon > 180. {
+            lon -= 360.;
+        } else if lon < -180. {
+            lon += 360.;
+        }
+
+        (lat.to_degrees(), lon)
+    }
+}
+
+/// Check `max_angle` (in degrees) is more than 0 and less than (or equal to, if `inclusive`)
+/// `limit`, and return it in radians.
+fn check_max_angle(max_angle: f64, limit: f64, inclusive: bool) -> f64 {
+    assert!(max_angle > 0. && (max_angle < limit || (inclusive && max_angle == limit)),
+        "max_angle must be more than 0 & less than {}, not {}", limit, max_angle);
+    max_angle.to_radians()
+}
+
+/// The orthographic projection, which shows the globe as it looks from far away in space,
+/// centred on a lat/lon.
+///
+/// Projected points are on the unit disc. No image is needed, so this can be used to project
+/// points for other things.
+///
+///```
+///use orthoproj::{Orthographic, Projection};
+///let proj = Orthographic::new(41.89889, 12.47337);
+///let (x, y) = proj.forward(51.50791, -0.12786).unwrap();
+///let (lat, lon) = proj.inverse(x, y).unwrap();
+///assert!((lat - 51.50791).abs() < 1e-9 && (lon + 0.12786).abs() < 1e-9);
+///
+///// Sydney is on the far side
+///assert_eq!(proj.forward(-33.86785, 151.20732), None);
+///```
+///
+/// It can also be on an ellipsoid, rather than a sphere, with `with_ellipsoid`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orthographic {
+    centre: Centre,
+    ellipsoid: Option<Ellipsoid>,
+}
+
+impl Orthographic {
+    /// The orthographic projection centred on `lat` and `lon`, in degrees
+    pub fn new<F: Float>(lat: F, lon: F) -> Self {
+        Orthographic{ centre: Centre::new(lat.to_f64(), lon.to_f64()), ellipsoid: None }
+    }
+
+    /// Project onto `ellipsoid`, rather than a sphere. This is the same as PROJ's `+proj=ortho`
+    /// with `+ellps`. Lat/lon are geodetic, and the map is of an ellipsoid with an equatorial
+    /// radius of 1, so multiply by `ellipsoid.semi_major()` for metres.
+    ///
+    /// The map is an ellipse, a little shorter than it is wide, and (unless it's centred on the
+    /// equator or a pole) its middle is a little off the centre of the map.
+    ///
+    ///```
+    ///use orthoproj::{Ellipsoid, Orthographic, Projection};
+    ///let proj = Orthographic::new(41.89889, 12.47337).with_ellipsoid(Ellipsoid::WGS84);
+    ///let (x, y) = proj.forward(51.50791, -0.12786).unwrap();
+    ///let (e, n) = (x * Ellipsoid::WGS84.semi_major(), y * Ellipsoid::WGS84.semi_major());
+    ///assert!((e + 867_848.376).abs() < 1e-3 && (n - 1_127_174.378).abs() < 1e-3);
+    ///```
+    pub fn with_ellipsoid(mut self, ellipsoid: Ellipsoid) -> Self {
+        self.ellipsoid = Some(ellipsoid);
+        self
+    }
+
+    /// `forward` on the ellipsoid, from EPSG guidance note 7-2, §3.3.5
+    fn forward_ellipsoid(&self, e2: f64, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (sin_lat0, cos_lat0) = self.centre.lat.sin_cos();
+        let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+        let (sin_dlon, cos_dlon) = (lon.to_radians() - self.centre.lon).sin_cos();
+        // Is the ground facing us: the normal there, dotted with the normal at the centre, which
+        // is the way we're looking. A tiny bit past the edge is allowed, for rounding, like PROJ.
+        if sin_lat0*sin_lat + cos_lat0*cos_lat*cos_dlon < -1e-10 {
+            return None;
+        }
+        // The radius of curvature in the prime vertical, at lat & the centre
+        let nu = 1. / (1. - e2*sin_lat*sin_lat).sqrt();
+        let nu0 = 1. / (1. - e2*sin_lat0*sin_lat0).sqrt();
+        let x = nu * cos_lat * sin_dlon;
+        let y = nu * (sin_lat*cos_lat0 - cos_lat*sin_lat0*cos_dlon) + e2 * (nu0*sin_lat0 - nu*sin_lat) * cos_lat0;
+        Some((x, y))
+    }
+
+    /// `inverse` on the ellipsoid. Go from the point on the map straight towards the viewer, to
+    /// where that meets the ellipsoid, with the centre's meridian along `x` = 0 in 3D.
+    fn inverse_ellipsoid(&self, e2: f64, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (sin_lat0, cos_lat0) = self.centre.lat.sin_cos();
+        let nu0 = 1. / (1. - e2*sin_lat0*sin_lat0).sqrt();
+        // The point on the plane of the map, in 3D: the centre, plus x east & y north from it
+        let q = [
+            nu0*cos_lat0 - y*sin_lat0,
+            x,
+            nu0*(1. - e2)*sin_lat0 + y*cos_lat0,
+        ];
+        // Looking along the normal at the centre
+        let d = [cos_lat0, 0., sin_lat0];
+        // Points on the ellipsoid have X² + Y² + Z²/(1 - e²) = 1, so solve for q + t d
+        let k = 1. / (1. - e2);
+        let a = d[0]*d[0] + d[1]*d[1] + k*d[2]*d[2];
+        let b = q[0]*d[0] + q[1]*d[1] + k*q[2]*d[2];
+        let c = q[0]*q[0] + q[1]*q[1] + k*q[2]*q[2] - 1.;
+        let discriminant = b*b - a*c;
+        if discriminant < 0. {
+            return None;
+        }
+        // The side nearer the viewer
+        let t = (-b + discriminant.sqrt()) / a;
+        let p = [q[0] + t*d[0], q[1] + t*d[1], q[2] + t*d[2]];
+
+        let lat = p[2].atan2((1. - e2) * p[0].hypot(p[1]));
+        let lon = self.centre.lon.to_degrees() + p[1].atan2(p[0]).to_degrees();
+        Some((lat.to_degrees(), (lon + 180.).rem_euclid(360.) - 180.))
+    }
+}
+
+impl Projection for Orthographic {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if let Some(ellipsoid) = self.ellipsoid {
+            return self.forward_ellipsoid(ellipsoid.eccentricity_squared(), lat, lon);
+        }
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        // is it the far side of the globe
+        if cos_c < 0. {
+            return None;
+        }
+        Some((x, y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if let Some(ellipsoid) = self.ellipsoid {
+            return self.inverse_ellipsoid(ellipsoid.eccentricity_squared(), x, y);
+        }
+        let rho = (x*x + y*y).sqrt();
+        if rho > 1. {
+            return None;
+        }
+        Some(self.centre.inverse(x, y, rho, rho.asin()))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        // On the ellipsoid too, since it's where the normal is at right angles to the way we're
+        // looking, and the normal points the same way as lat/lon on a sphere
+        Boundary::Circle(90.)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        match self.ellipsoid {
+            None => (1., 1.),
+            Some(ellipsoid) => {
+                // The edge is an ellipse, as tall as the ellipsoid looks from this latitude, with
+                // its middle where the centre of the ellipsoid is on the map
+                let e2 = ellipsoid.eccentricity_squared();
+                let (sin_lat0, cos_lat0) = self.centre.lat.sin_cos();
+                let nu0 = 1. / (1. - e2*sin_lat0*sin_lat0).sqrt();
+                let middle = e2 * nu0 * sin_lat0 * cos_lat0;
+                let half_height = (sin_lat0*sin_lat0 + (1. - e2)*cos_lat0*cos_lat0).sqrt();
+                (1., middle.abs() + half_height)
+            },
+        }
+    }
+}
+
+/// The gnomonic projection, where every great circle is a straight line. It can't show more than
+/// a hemisphere, and things get very stretched towards the edge, so by default only points up to
+/// 60° from the centre are shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gnomonic {
+    centre: Centre,
+    max_angle: f64,
+}
+
+impl Gnomonic {
+    /// The gnomonic projection centred on `lat` and `lon`, in degrees
+    pub fn new<F: Float>(lat: F, lon: F) -> Self {
+        Gnomonic{ centre: Centre::new(lat.to_f64(), lon.to_f64()), max_angle: 60f64.to_radians() }
+    }
+
+    /// Show points up to `max_angle` degrees from the centre. Panics unless it's between 0 & 90.
+    pub fn with_max_angle<F: Float>(mut self, max_angle: F) -> Self {
+        self.max_angle = check_max_angle(max_angle.to_f64(), 90., false);
+        self
+    }
+}
+
+impl Projection for Gnomonic {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        if cos_c < self.max_angle.cos() {
+            return None;
+        }
+        Some((x / cos_c, y / cos_c))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let rho = (x*x + y*y).sqrt();
+        let c = rho.atan();
+        if c > self.max_angle {
+            return None;
+        }
+        Some(self.centre.inverse(x, y, rho, c))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Circle(self.max_angle.to_degrees())
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        let r = self.max_angle.tan();
+        (r, r)
+    }
+}
+
+/// The stereographic projection, which keeps the shapes of small things the same (it's
+/// conformal), and where every circle on the globe is a circle on the map. By default it shows
+/// the hemisphere around the centre.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stereographic {
+    centre: Centre,
+    max_angle: f64,
+}
+
+impl Stereographic {
+    /// The stereographic projection centred on `lat` and `lon`, in degrees
+    pub fn new<F: Float>(lat: F, lon: F) -> Self {
+        Stereographic{ centre: Centre::new(lat.to_f64(), lon.to_f64()), max_angle: PI / 2. }
+    }
+
+    /// Show points up to `max_angle` degrees from the centre. Panics unless it's between 0 & 180.
+    pub fn with_max_angle<F: Float>(mut self, max_angle: F) -> Self {
+        self.max_angle = check_max_angle(max_angle.to_f64(), 180., false);
+        self
+    }
+}
+
+impl Projection for Stereographic {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        if cos_c < self.max_angle.cos() {
+            return None;
+        }
+        let k = 2. / (1. + cos_c);
+        Some((k * x, k * y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let rho = (x*x + y*y).sqrt();
+        let c = 2. * (rho / 2.).atan();
+        if c > self.max_angle {
+            return None;
+        }
+        Some(self.centre.inverse(x, y, rho, c))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Circle(self.max_angle.to_degrees())
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        let r = 2. * (self.max_angle / 2.).tan();
+        (r, r)
+    }
+}
+
+/// The Lambert azimuthal equal-area projection, where things have the same area on the map as on
+/// the globe. By default it shows the whole world, in a disc of radius 2.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LambertAzimuthalEqualArea {
+    centre: Centre,
+    max_angle: f64,
+}
+
+impl LambertAzimuthalEqualArea {
+    /// The Lambert azimuthal equal-area projection centred on `lat` and `lon`, in degrees
+    pub fn new<F: Float>(lat: F, lon: F) -> Self {
+        LambertAzimuthalEqualArea{ centre: Centre::new(lat.to_f64(), lon.to_f64()), max_angle: PI }
+    }
+
+    /// Show points up to `max_angle` degrees from the centre. Panics unless it's more than 0, and
+    /// at most 180.
+    pub fn with_max_angle<F: Float>(mut self, max_angle: F) -> Self {
+        self.max_angle = check_max_angle(max_angle.to_f64(), 180., true);
+        self
+    }
+}
+
+impl Projection for LambertAzimuthalEqualArea {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        // The point opposite the centre is the whole edge of the map, so it has no one place
+        if cos_c < self.max_angle.cos() || cos_c <= -1. {
+            return None;
+        }
+        let k = (2. / (1. + cos_c)).sqrt();
+        Some((k * x, k * y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let rho = (x*x + y*y).sqrt();
+        if rho > 2. {
+            return None;
+        }
+        let c = 2. * (rho / 2.).asin();
+        if c > self.max_angle {
+            return None;
+        }
+        Some(self.centre.inverse(x, y, rho, c))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Circle(self.max_angle.to_degrees())
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        let r = 2. * (self.max_angle / 2.).sin();
+        (r, r)
+    }
+}
+
+/// The azimuthal equidistant projection, where the distance from the centre on the map is the
+/// same as on the globe. By default it shows the whole world, in a disc of radius π.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AzimuthalEquidistant {
+    centre: Centre,
+    max_angle: f64,
+}
+
+impl AzimuthalEquidistant {
+    /// The azimuthal equidistant projection centred on `lat` and `lon`, in degrees
+    pub fn new<F: Float>(lat: F, lon: F) -> Self {
+        AzimuthalEquidistant{ centre: Centre::new(lat.to_f64(), lon.to_f64()), max_angle: PI }
+    }
+
+    /// Show points up to `max_angle` degrees from the centre. Panics unless it's more than 0, and
+    /// at most 180.
+    pub fn with_max_angle<F: Float>(mut self, max_angle: F) -> Self {
+        self.max_angle = check_max_angle(max_angle.to_f64(), 180., true);
+        self
+    }
+}
+
+impl Projection for AzimuthalEquidistant {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        // The point opposite the centre is the whole edge of the map, so it has no one place
+        if cos_c < self.max_angle.cos() || cos_c <= -1. {
+            return None;
+        }
+        // x & y are sin(c) from the centre, so this is more accurate than acos(cos_c)
+        let sin_c = (x*x + y*y).sqrt();
+        if sin_c == 0. {
+            return Some((0., 0.));
+        }
+        let k = sin_c.atan2(cos_c) / sin_c;
+        Some((k * x, k * y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let rho = (x*x + y*y).sqrt();
+        if rho > self.max_angle {
+            return None;
+        }
+        Some(self.centre.inverse(x, y, rho, rho))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Circle(self.max_angle.to_degrees())
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (self.max_angle, self.max_angle)
+    }
+}
+
+/// The (vertical) general perspective projection: the globe as seen from a satellite at some
+/// altitude above the centre. The closer it is, the less of the globe can be seen. From very far
+/// away, this is the orthographic projection.
+///
+///```
+///use orthoproj::{OrthoProj, Perspective};
+///// The International Space Station's view of Rome
+///let image = OrthoProj::new_with_projection(500, 500, Perspective::new(41.89889, 12.47337, 408.), 0);
+///```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Perspective {
+    centre: Centre,
+    /// Distance from the centre of the globe to the viewer, in globe radii
+    p: f64,
+}
+
+impl Perspective {
+    /// The view from `altitude` km above `lat` and `lon` (in degrees), on a globe the size of the
+    /// Earth. Panics unless `altitude` is more than 0.
+    pub fn new<F: Float>(lat: F, lon: F, altitude: F) -> Self {
+        Self::new_with_distance(lat.to_f64(), lon.to_f64(), 1. + altitude.to_f64() / EARTH_RADIUS_KM)
+    }
+
+    /// The view from `distance` globe radii from the centre of the globe, above `lat` and `lon`
+    /// (in degrees). Panics unless `distance` is more than 1.
+    pub fn new_with_distance<F: Float>(lat: F, lon: F, distance: F) -> Self {
+        let distance = distance.to_f64();
+        assert!(distance > 1., "viewer must be outside the globe");
+        Perspective{ centre: Centre::new(lat.to_f64(), lon.to_f64()), p: distance }
+    }
+}
+
+impl Projection for Perspective {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        let (cos_c, x, y) = self.centre.forward(lat, lon);
+        // Past the horizon
+        if cos_c < 1. / self.p {
+            return None;
+        }
+        // Scaled so that the centre of the map is the same as the orthographic projection
+        let k = (self.p - 1.) / (self.p - cos_c);
+        Some((k * x, k * y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let p = self.p;
+        let rho = (x*x + y*y).sqrt();
+        let d = 1. - rho*rho * (p + 1.) / (p - 1.);
+        if d < 0. {
+            return None;
+        }
+        if rho == 0. {
+            return Some(self.centre.degrees());
+        }
+        let sin_c = (p - d.sqrt()) / ((p - 1.) / rho + rho / (p - 1.));
+        Some(self.centre.inverse(x, y, rho, sin_c.min(1.).asin()))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        self.centre.degrees()
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Circle((1. / self.p).acos().to_degrees())
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        let r = ((self.p - 1.) / (self.p + 1.)).sqrt();
+        (r, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::{check_round_trip, close};
+
+    #[test]
+    fn test_forward() {
+        let proj = Orthographic::new(0., 0.);
+        assert_eq!(proj.forward(0., 0.), Some((0., 0.)));
+        assert!(close(proj.forward(0., 90.).unwrap(), (1., 0.)));
+        assert!(close(proj.forward(90., 0.).unwrap(), (0., 1.)));
+        assert!(close(proj.forward(30., 0.).unwrap(), (0., 0.5)));
+        assert_eq!(proj.forward(0., 180.), None);
+        assert_eq!(proj.forward(10., -100.), None);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let proj = Orthographic::new(45., 170.);
+        assert_eq!(proj.inverse(0., 0.), Some((45., 170.)));
+        assert_eq!(proj.inverse(0.8, 0.8), None);
+        let (lat, lon) = proj.inverse(0.5, 0.).unwrap();
+        // East of the centre, past 180°, so it's wrapped around
+        assert!((-180.0..-150.).contains(&lon), "{}", lon);
+        assert!(lat < 45.);
+        assert!(close(proj.forward(lat, lon).unwrap(), (0.5, 0.)));
+    }
+
+    #[test]
+    fn test_azimuthal() {
+        // How far from the centre 45° & 90° north are
+        let gnomonic = Gnomonic::new(0., 0.);
+        assert!(close(gnomonic.forward(45., 0.).unwrap(), (0., 1.)));
+        assert_eq!(gnomonic.forward(61., 0.), None);
+        assert_eq!(gnomonic.with_max_angle(80.).forward(61., 0.).map(|_| ()), Some(()));
+
+        let stereographic = Stereographic::new(0., 0.);
+        assert!(close(stereographic.forward(90., 0.).unwrap(), (0., 2.)));
+        assert_eq!(stereographic.forward(0., 100.), None);
+        assert!(stereographic.with_max_angle(120.).forward(0., 100.).is_some());
+
+        let laea = LambertAzimuthalEqualArea::new(0., 0.);
+        assert!(close(laea.forward(0., -90.).unwrap(), (-2f64.sqrt(), 0.)));
+        let (x, y) = laea.forward(0., 179.9).unwrap();
+        assert!((x - 2.).abs() < 1e-5 && y.abs() < 1e-9);
+
+        let aeqd = AzimuthalEquidistant::new(0., 0.);
+        assert!(close(aeqd.forward(-90., 0.).unwrap(), (0., -PI / 2.)));
+        assert!(close(aeqd.forward(0., 179.).unwrap(), (179f64.to_radians(), 0.)));
+        assert_eq!(aeqd.inverse(3.2, 0.), None);
+
+        // From geostationary orbit, you can see 81.3° from the centre
+        let geostationary = Perspective::new(0., 0., 35786.);
+        match geostationary.boundary() {
+            Boundary::Circle(angle) => assert!((angle - 81.3).abs() < 0.05),
+            b => panic!("{:?}", b),
+        }
+        assert!(geostationary.forward(0., 81.).is_some());
+        assert!(geostationary.forward(0., 82.).is_none());
+        // Near the centre, it's the same size as orthographic
+        assert!(close(geostationary.forward(0., 1e-4).unwrap(), Orthographic::new(0., 0.).forward(0., 1e-4).unwrap()));
+    }
+
+    #[test]
+    fn test_ellipsoid() {
+        // Worked out from the points' earth-centred 3D coordinates (in metres), and the east &
+        // north directions at the centre. To check them against PROJ (lon first):
+        //   echo 9 50 | proj -f %.3f +proj=ortho +ellps=WGS84 +lat_0=55 +lon_0=5
+        //   echo 100 -60 | proj -f %.3f +proj=ortho +ellps=WGS84 +lat_0=-33.9 +lon_0=151.2
+        //   echo 90 0 | proj -f %.3f +proj=ortho +ellps=WGS84 +lat_0=90 +lon_0=0
+        let wgs84 = Ellipsoid::WGS84;
+        let metres = |proj: Orthographic, lat, lon| {
+            let (x, y) = proj.forward(lat, lon).unwrap();
+            (x * wgs84.semi_major(), y * wgs84.semi_major())
+        };
+        let near = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3;
+        let proj = Orthographic::new(55., 5.).with_ellipsoid(wgs84);
+        assert!(near(metres(proj, 50., 9.), (286550.114, -547480.621)));
+        let proj = Orthographic::new(-33.9, 151.2).with_ellipsoid(wgs84);
+        assert!(near(metres(proj, -60., 100.), (-2491624.982, -3467909.697)));
+        // From above the pole, the equator is a circle the size of the equator
+        let proj = Orthographic::new(90., 0.).with_ellipsoid(wgs84);
+        assert!(near(metres(proj, 0., 0.), (0., -6378137.)));
+        assert!(near(metres(proj, 0., 90.), (6378137., 0.)));
+
+        // Not quite as tall as it is wide, from the equator
+        let proj = Orthographic::new(0., 0.).with_ellipsoid(wgs84);
+        let polar = 1. - wgs84.flattening();
+        assert!(close(proj.forward(90., 0.).unwrap(), (0., polar)));
+        assert!(close(proj.forward(0., 90.).unwrap(), (1., 0.)));
+        assert!(close(proj.extent(), (1., polar)));
+        assert_eq!(proj.inverse(0., polar + 1e-6), None);
+        assert!(proj.inverse(0., polar - 1e-9).unwrap().0 > 89.9);
+        assert_eq!(proj.forward(0., 91.), None);
+
+        // Going from the middle, the edge is just inside the extent, all the way around
+        let proj = Orthographic::new(40., 10.).with_ellipsoid(wgs84);
+        let (extent_x, extent_y) = proj.extent();
+        for i in 0..360 {
+            let (sin, cos) = (i as f64).to_radians().sin_cos();
+            let mut r = 0.;
+            while proj.inverse(r * cos, r * sin).is_some() {
+                r += 1e-3;
+            }
+            assert!((r * cos).abs() <= extent_x + 1e-3 && (r * sin).abs() <= extent_y + 1e-3);
+        }
+
+        // Geodetic latitude, so the centre is where the ground is flat, facing us
+        let (lat, lon) = proj.inverse(0., 0.).unwrap();
+        assert!((lat - 40.).abs() < 1e-9 && (lon - 10.).abs() < 1e-9);
+        // A sphere is the same as no ellipsoid
+        let sphere = Orthographic::new(40., 10.).with_ellipsoid(Ellipsoid::new(1., 0.));
+        let plain = Orthographic::new(40., 10.);
+        for &(lat, lon) in &[(40., 10.), (0., 0.), (80., -30.), (-10., 60.)] {
+            assert!(close(sphere.forward(lat, lon).unwrap(), plain.forward(lat, lon).unwrap()));
+        }
+        assert!(close(sphere.extent(), plain.extent()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_max_angle_too_big() {
+        Gnomonic::new(0., 0.).with_max_angle(90.);
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips(lat0 in -90f64..90., lon0 in -180f64..180., lat in -90f64..90., lon in -180f64..180., altitude in 1f64..100000.) {
+            // Right at the edge of the orthographic projection, asin loses too much precision
+            if Orthographic::new(lat0, lon0).forward(lat, lon).is_some_and(|(x, y)| x*x + y*y < 0.99) {
+                check_round_trip(Orthographic::new(lat0, lon0), lat, lon).unwrap();
+            }
+            let ellipsoidal = Orthographic::new(lat0, lon0).with_ellipsoid(Ellipsoid::WGS84);
+            if Orthographic::new(lat0, lon0).forward(lat, lon).is_some_and(|(x, y)| x*x + y*y < 0.99) {
+                check_round_trip(ellipsoidal, lat, lon).unwrap();
+            }
+            check_round_trip(Gnomonic::new(lat0, lon0), lat, lon).unwrap();
+            check_round_trip(Stereographic::new(lat0, lon0).with_max_angle(170.), lat, lon).unwrap();
+            check_round_trip(LambertAzimuthalEqualArea::new(lat0, lon0).with_max_angle(179.), lat, lon).unwrap();
+            check_round_trip(AzimuthalEquidistant::new(lat0, lon0).with_max_angle(179.), lat, lon).unwrap();
+            let perspective = Perspective::new(lat0, lon0, altitude);
+            if perspective.forward(lat, lon).is_some_and(|(x, y)| (x*x + y*y).sqrt() < 0.99 * perspective.extent().0) {
+                check_round_trip(perspective, lat, lon).unwrap();
+            }
+        }
+
+        #[test]
+        fn inverse_is_on_the_map(x in -4f64..4., y in -4f64..4.) {
+            fn check<P: Projection>(proj: P, x: f64, y: f64) {
+                if let Some((lat, lon)) = proj.inverse(x, y) {
+                    assert!((-90.0..=90.).contains(&lat) && (-180.0..=180.).contains(&lon));
+                    assert!(proj.forward(lat, lon).is_some(), "{} {} -> {} {}", x, y, lat, lon);
+                }
+            }
+            check(Orthographic::new(10., 20.), x, y);
+            check(Orthographic::new(10., 20.).with_ellipsoid(Ellipsoid::WGS84), x, y);
+            check(Gnomonic::new(10., 20.), x, y);
+            check(Stereographic::new(10., 20.), x, y);
+            check(LambertAzimuthalEqualArea::new(10., 20.), x, y);
+            check(AzimuthalEquidistant::new(10., 20.), x, y);
+            check(Perspective::new(10., 20., 1000.), x, y);
+        }
+    }
+}
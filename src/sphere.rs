@@ -0,0 +1,80 @@
+//! Points on the unit sphere as 3D vectors, for drawing along great circles.
+
+/// A point on (or near) the unit sphere. `x` points at (0, 0), `y` at (0, 90) & `z` at the north
+/// pole.
+pub type Vec3 = [f64; 3];
+
+/// The unit vector for `lat`/`lon`, in degrees
+pub fn from_pos(lat: f64, lon: f64) -> Vec3 {
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+    [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]
+}
+
+pub fn length(a: Vec3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Angle between `a` & `b`, in radians
+pub fn angle(a: Vec3, b: Vec3) -> f64 {
+    length(cross(a, b)).atan2(dot(a, b))
+}
+
+/// The point `t` (0 to 1) of the way along the great circle from `a` to `b`, which are `angle`
+/// apart. `angle` must not be 0 or π.
+pub fn slerp(a: Vec3, b: Vec3, angle: f64, t: f64) -> Vec3 {
+    let sin_angle = angle.sin();
+    let fa = ((1. - t) * angle).sin() / sin_angle;
+    let fb = (t * angle).sin() / sin_angle;
+    [fa*a[0] + fb*b[0], fa*a[1] + fb*b[1], fa*a[2] + fb*b[2]]
+}
+
+/// The point half way between `a` and `b`, on the unit sphere. `a` and `b` must not be opposite
+/// each other.
+pub fn midpoint(a: Vec3, b: Vec3) -> Vec3 {
+    let m = [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+    let len = length(m);
+    [m[0]/len, m[1]/len, m[2]/len]
+}
+
+/// The lat/lon, in degrees, of `p`
+pub fn to_pos(p: Vec3) -> (f64, f64) {
+    let lat = p[2].atan2((p[0]*p[0] + p[1]*p[1]).sqrt());
+    let lon = p[1].atan2(p[0]);
+    (lat.to_degrees(), lon.to_degrees())
+}
+
+/// `a` multiplied by `s`
+pub fn scale(a: Vec3, s: f64) -> Vec3 {
+    [a[0]*s, a[1]*s, a[2]*s]
+}
+
+pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// Points every 1° around the circle of points `angle` radians from `centre`, anticlockwise (seen
+/// from above `centre`). The last point isn't repeated. These are joined by great circles when
+/// they're drawn, which aren't quite the same as a small circle like this, but they're very close
+/// over 1°.
+pub fn small_circle(centre: Vec3, angle: f64) -> Vec<Vec3> {
+    let (lat, lon) = to_pos(centre);
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+    let east = [-sin_lon, cos_lon, 0.];
+    let north = [-sin_lat*cos_lon, -sin_lat*sin_lon, cos_lat];
+    let (sin_d, cos_d) = angle.sin_cos();
+    (0..360).map(|i| {
+        let (sin_a, cos_a) = (i as f64).to_radians().sin_cos();
+        let direction = add(scale(east, cos_a), scale(north, sin_a));
+        add(scale(centre, cos_d), scale(direction, sin_d))
+    }).collect()
+}
@@ -0,0 +1,477 @@
+//! Drawing orthographic maps as SVG vector images.
+//!
+//! This uses the same projection as `OrthoProj`, but everything is kept as continuous `f64`
+//! coordinates, rather than being turned into pixels, so it stays sharp when printed.
+//!
+//! ```
+//! use orthoproj::svg::{Map, Style};
+//!
+//! let mut map = Map::new(500, 41.89889, 12.47337);
+//! map.set_globe_style(Style::new().fill("#c6e2ff").stroke("black", 1.));
+//! map.set_graticule(15., Style::new().stroke("#999", 0.5));
+//!
+//! map.add_layer(Style::new().fill("#2a2").stroke("#060", 0.5))
+//!     .polygon(&[(36., -10.), (36., 30.), (60., 30.), (60., -10.)]);
+//! map.add_layer(Style::new().stroke("red", 2.))
+//!     .line(&[(41.89889, 12.47337), (40.71427, -74.00597)]);
+//! map.add_layer(Style::new().fill("black"))
+//!     .point(41.89889, 12.47337, 3.)
+//!     .point(51.50791, -0.12786, 3.);
+//!
+//! let svg: String = map.to_svg();
+//! ```
+
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+use {Float, Orthographic, View};
+use clip::{self, Path};
+use graticule;
+use sphere;
+
+/// How to draw things: the fill colour, and the colour & width of the outline. Colours are any
+/// SVG colour, like `"red"` or `"#ff0000"`. By default, nothing is filled or outlined.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    fill: Option<String>,
+    stroke: Option<(String, f64)>,
+    opacity: Option<f64>,
+}
+
+impl Style {
+    /// A style which doesn't fill or outline anything
+    pub fn new() -> Self {
+        Style::default()
+    }
+
+    /// Fill shapes with `colour`
+    pub fn fill(mut self, colour: &str) -> Self {
+        self.fill = Some(colour.to_string());
+        self
+    }
+
+    /// Outline shapes with `colour`, with lines `width` pixels wide
+    pub fn stroke(mut self, colour: &str, width: f64) -> Self {
+        self.stroke = Some((colour.to_string(), width));
+        self
+    }
+
+    /// Make everything `opacity` (0 to 1) opaque
+    pub fn opacity(mut self, opacity: f64) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// The SVG attributes for this style
+    fn attributes(&self) -> String {
+        let mut attrs = String::new();
+        match self.fill {
+            None => attrs.push_str(" fill=\"none\""),
+            Some(ref fill) => { let _ = write!(attrs, " fill=\"{}\"", escape(fill)); },
+        }
+        match self.stroke {
+            None => attrs.push_str(" stroke=\"none\""),
+            Some((ref stroke, width)) => {
+                let _ = write!(attrs, " stroke=\"{}\" stroke-width=\"{}\" stroke-linejoin=\"round\" stroke-linecap=\"round\"",
+                               escape(stroke), width);
+            },
+        }
+        if let Some(opacity) = self.opacity {
+            let _ = write!(attrs, " opacity=\"{}\"", opacity);
+        }
+        attrs
+    }
+}
+
+/// Things to draw on a `Map`
+#[derive(Clone, Debug)]
+enum Shape {
+    /// A circle at `(lat, lon)` with a radius in pixels
+    Point((f64, f64), f64),
+    /// `(lat, lon)` points, joined with great circles
+    Line(Vec<(f64, f64)>),
+    /// The exterior ring, then the holes
+    Polygon(Vec<Vec<(f64, f64)>>),
+}
+
+/// `(lat, lon)` `points` as `f64`s
+fn to_f64s<F: Float>(points: &[(F, F)]) -> Vec<(f64, f64)> {
+    points.iter().map(|&(lat, lon)| (lat.to_f64(), lon.to_f64())).collect()
+}
+
+/// A group of shapes on a `Map`, all drawn with the same `Style`.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    style: Style,
+    shapes: Vec<Shape>,
+}
+
+impl Layer {
+    /// Draw a circle, `radius` pixels in size, at `lat`/`lon`, if it's on the visible side of the
+    /// globe.
+    pub fn point<F: Float>(&mut self, lat: F, lon: F, radius: F) -> &mut Self {
+        self.shapes.push(Shape::Point((lat.to_f64(), lon.to_f64()), radius.to_f64()));
+        self
+    }
+
+    /// Draw a line through all the `(lat, lon)` `points`, with great circles between each point.
+    /// It stops at the edge of the globe, where it goes around the back.
+    pub fn line<F: Float>(&mut self, points: &[(F, F)]) -> &mut Self {
+        self.shapes.push(Shape::Line(to_f64s(points)));
+        self
+    }
+
+    /// Draw a polygon with this ring of `(lat, lon)` points, with great circles between each
+    /// point. Like `OrthoProj::fill_polygon`, the inside is the smaller part of the globe, and
+    /// where it goes around the back, it's closed along the edge of the globe.
+    pub fn polygon<F: Float>(&mut self, ring: &[(F, F)]) -> &mut Self {
+        self.polygon_with_holes::<F, &[(F, F)]>(ring, &[])
+    }
+
+    /// Like `polygon`, but with these `holes` in it.
+    pub fn polygon_with_holes<F, R>(&mut self, exterior: &[(F, F)], holes: &[R]) -> &mut Self
+        where F: Float, R: AsRef<[(F, F)]>
+    {
+        let mut rings = vec![to_f64s(exterior)];
+        rings.extend(holes.iter().map(|h| to_f64s(h.as_ref())));
+        self.shapes.push(Shape::Polygon(rings));
+        self
+    }
+}
+
+/// An orthographic map, which can be saved as an SVG image.
+///
+/// Like `OrthoProj`, maps are square, with the globe in the middle, by default, but they can be
+/// any size, with the globe anywhere.
+#[derive(Clone, Debug)]
+pub struct Map {
+    view: View<Orthographic>,
+    width: u32,
+    height: u32,
+    globe: Style,
+    graticule: Option<(f64, Style)>,
+    layers: Vec<Layer>,
+}
+
+impl Map {
+    /// Create a new map with width & height of `size`, centred on `lat` and `lon`.
+    pub fn new<F: Float>(size: u32, lat: F, lon: F) -> Self {
+        Self::new_with_dimensions(size, size, lat, lon)
+    }
+
+    /// Create a new map which is `width` by `height`, centred on `lat` and `lon`. The globe is in
+    /// the middle of the image, and as large as will fit.
+    pub fn new_with_dimensions<F: Float>(width: u32, height: u32, lat: F, lon: F) -> Self {
+        Map{
+            view: View::new(width, height, Orthographic::new(lat, lon)),
+            width, height,
+            globe: Style::new().stroke("black", 1.),
+            graticule: None,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Change the radius of the globe to `radius` pixels. Panics unless `radius` is more than 0,
+    /// and finite.
+    pub fn with_radius<F: Float>(mut self, radius: F) -> Self {
+        let radius = radius.to_f64();
+        assert!(radius > 0. && radius.is_finite(), "radius must be more than 0, and finite, not {}", radius);
+        self.view.radius = radius;
+        self
+    }
+
+    /// Move the centre of the globe `dx` pixels right and `dy` pixels down from the centre of the
+    /// image.
+    pub fn with_offset<F: Float>(mut self, dx: F, dy: F) -> Self {
+        self.view.cx = self.width as f64 / 2. + dx.to_f64();
+        self.view.cy = self.height as f64 / 2. + dy.to_f64();
+        self
+    }
+
+    /// Turn the globe around its centre, so that the direction `degrees` clockwise from north is
+    /// up, like `OrthoProj::with_rotation`.
+    pub fn with_rotation<F: Float>(mut self, degrees: F) -> Self {
+        self.view.rotation = degrees.to_f64().to_radians().sin_cos();
+        self
+    }
+
+    /// How to draw the globe. The fill is under everything else, and the outline is on top. By
+    /// default it has a black outline, 1 pixel wide.
+    pub fn set_globe_style(&mut self, style: Style) {
+        self.globe = style;
+    }
+
+    /// Draw lines of latitude & longitude every `step` degrees from the equator & prime meridian,
+    /// with `style`. These are the same lines as `OrthoProj::draw_graticule` draws.
+    pub fn set_graticule<F: Float>(&mut self, step: F, style: Style) {
+        self.graticule = Some((step.to_f64(), style));
+    }
+
+    /// Add a new layer, drawn with `style`, on top of the others, and return it, so shapes can be
+    /// added to it.
+    pub fn add_layer(&mut self, style: Style) -> &mut Layer {
+        self.layers.push(Layer{ style, shapes: Vec::new() });
+        self.layers.last_mut().unwrap()
+    }
+
+    /// Where on the image `lat`/`lon` is. `None` if it's on the far side of the globe.
+    pub fn xy_for_pos<F: Float>(&self, lat: F, lon: F) -> Option<(f64, f64)> {
+        self.view.xy_for_pos(lat.to_f64(), lon.to_f64())
+    }
+
+    /// This map as an SVG document
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">",
+                         self.width, self.height);
+
+        let globe = format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\"", num(self.view.cx), num(self.view.cy), num(self.view.radius));
+        if self.globe.fill.is_some() {
+            let fill = Style{ stroke: None, ..self.globe.clone() };
+            let _ = writeln!(svg, "{}{}/>", globe, fill.attributes());
+        }
+
+        if let Some((step, ref style)) = self.graticule {
+            let d = path_data(&self.graticule_paths(step), false);
+            let _ = writeln!(svg, "<path d=\"{}\"{}/>", d, Style{ fill: None, ..style.clone() }.attributes());
+        }
+
+        for layer in &self.layers {
+            let _ = writeln!(svg, "<g{}>", layer.style.attributes());
+            for shape in &layer.shapes {
+                self.write_shape(&mut svg, shape);
+            }
+            svg.push_str("</g>\n");
+        }
+
+        if self.globe.stroke.is_some() {
+            let outline = Style{ fill: None, ..self.globe.clone() };
+            let _ = writeln!(svg, "{}{}/>", globe, outline.attributes());
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write this map, as an SVG document, to `w`
+    pub fn write_svg<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(self.to_svg().as_bytes())
+    }
+
+    /// Add the SVG element for `shape` to `svg`, if any of it is visible
+    fn write_shape(&self, svg: &mut String, shape: &Shape) {
+        match *shape {
+            Shape::Point((lat, lon), radius) => {
+                if let Some((x, y)) = self.view.xy_for_pos(lat, lon) {
+                    let _ = writeln!(svg, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>", num(x), num(y), num(radius));
+                }
+            },
+            Shape::Line(ref points) => {
+                let points: Vec<_> = points.iter().map(|&(lat, lon)| sphere::from_pos(lat, lon)).collect();
+                let paths = clip::linestring(&self.view, &points);
+                if !paths.is_empty() {
+                    let _ = writeln!(svg, "<path d=\"{}\" fill=\"none\"/>", path_data(&paths, false));
+                }
+            },
+            Shape::Polygon(ref rings) => {
+                let mut paths = Vec::new();
+                for ring in rings {
+                    let ring: Vec<_> = ring.iter().map(|&(lat, lon)| sphere::from_pos(lat, lon)).collect();
+                    paths.extend(clip::ring(&self.view, &ring));
+                }
+                if !paths.is_empty() {
+                    let _ = writeln!(svg, "<path d=\"{}\" fill-rule=\"evenodd\"/>", path_data(&paths, true));
+                }
+            },
+        }
+    }
+
+    /// The visible parts of the lines of latitude & longitude every `step` degrees north, south,
+    /// east & west of the equator & prime meridian, the same lines as `OrthoProj::draw_graticule`
+    fn graticule_paths(&self, step: f64) -> Vec<Path> {
+        let mut paths = Vec::new();
+        for lon in graticule::meridians(step) {
+            paths.extend(clip::linestring(&self.view, &graticule::meridian(lon)));
+        }
+        for lat in graticule::parallels(step) {
+            paths.extend(clip::linestring(&self.view, &graticule::parallel(lat)));
+        }
+        paths
+    }
+}
+
+/// The SVG path data (the `d` attribute) for these `paths`.
+fn path_data(paths: &[Path], closed: bool) -> String {
+    let mut d = String::new();
+    for path in paths {
+        for (i, &(x, y)) in path.iter().enumerate() {
+            let _ = write!(d, "{}{} {}", if i == 0 { "M" } else { "L" }, num(x), num(y));
+        }
+        if closed {
+            d.push('Z');
+        }
+    }
+    d
+}
+
+/// Numbers in SVG, to 1/1000 of a pixel, without needless 0s
+fn num(x: f64) -> String {
+    let s = format!("{:.3}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+/// Escape `s` to go in an XML attribute
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Map, Style, num};
+    use {pixel_floor, OrthoProj};
+
+    #[test]
+    fn test_num() {
+        assert_eq!(num(1.), "1");
+        assert_eq!(num(1.5), "1.5");
+        assert_eq!(num(0.12345), "0.123");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(100.), "100");
+    }
+
+    #[test]
+    fn test_empty_map() {
+        let map = Map::new(100, 0., 0.);
+        assert_eq!(map.to_svg(), "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"100\" height=\"100\" viewBox=\"0 0 100 100\">\n\
+                                  <circle cx=\"50\" cy=\"50\" r=\"50\" fill=\"none\" stroke=\"black\" stroke-width=\"1\" stroke-linejoin=\"round\" stroke-linecap=\"round\"/>\n\
+                                  </svg>\n");
+    }
+
+    #[test]
+    fn test_points() {
+        let mut map = Map::new(100, 0., 0.);
+        map.set_globe_style(Style::new().fill("blue"));
+        map.add_layer(Style::new().fill("red").opacity(0.5))
+            .point(0., 0., 2.)
+            .point(0., 180., 2.)
+            .point(0., 30., 1.5);
+        let svg = map.to_svg();
+        assert!(svg.contains("<circle cx=\"50\" cy=\"50\" r=\"50\" fill=\"blue\" stroke=\"none\"/>"));
+        assert!(svg.contains("<g fill=\"red\" stroke=\"none\" opacity=\"0.5\">\n<circle cx=\"50\" cy=\"50\" r=\"2\"/>\n<circle cx=\"75\" cy=\"50\" r=\"1.5\"/>\n</g>"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+    }
+
+    #[test]
+    fn test_line() {
+        let mut map = Map::new(100, 0., 0.);
+        map.add_layer(Style::new().stroke("red", 1.)).line(&[(0., 0.), (0., 150.)]);
+        let svg = map.to_svg();
+        let d = svg.split("<path d=\"").nth(1).unwrap().split('"').next().unwrap();
+        assert!(d.starts_with("M50 50L"));
+        // Stops at the edge
+        assert!(d.ends_with("L100 50"), "{}", d);
+        assert!(!d[1..].contains('M'));
+
+        // All behind
+        let mut map = Map::new(100, 0., 0.);
+        map.add_layer(Style::new()).line(&[(0., 120.), (0., 150.)]);
+        assert!(!map.to_svg().contains("<path"));
+    }
+
+    #[test]
+    fn test_polygon() {
+        let mut map = Map::new(100, 0., 0.);
+        map.add_layer(Style::new().fill("green"))
+            .polygon_with_holes(&[(-10., -10.), (-10., 10.), (10., 10.), (10., -10.)], &[vec![(-1., -1.), (-1., 1.), (1., 1.), (1., -1.)]])
+            .polygon(&[(-10., 60.), (-10., 120.), (10., 120.), (10., 60.)]);
+        let svg = map.to_svg();
+        let paths: Vec<&str> = svg.split("<path d=\"").skip(1).map(|p| p.split('"').next().unwrap()).collect();
+        assert_eq!(paths.len(), 2);
+        // Exterior & hole
+        assert_eq!(paths[0].matches('M').count(), 2);
+        assert_eq!(paths[0].matches('Z').count(), 2);
+        // Goes along the edge of the globe
+        assert_eq!(paths[1].matches('M').count(), 1);
+        let max_x = paths[1].trim_matches(|c| c == 'M' || c == 'Z').split('L')
+            .map(|xy| xy.split(' ').next().unwrap().parse::<f64>().unwrap())
+            .fold(0., f64::max);
+        assert!(max_x > 99.99 && max_x <= 100., "{}", paths[1]);
+        assert!(svg.contains("fill-rule=\"evenodd\""));
+
+        // f32s are the same
+        let mut map32 = Map::new(100, 0f32, 0f32);
+        map32.add_layer(Style::new().fill("green"))
+            .polygon_with_holes(&[(-10f32, -10f32), (-10., 10.), (10., 10.), (10., -10.)], &[vec![(-1f32, -1f32), (-1., 1.), (1., 1.), (1., -1.)]])
+            .polygon(&[(-10f32, 60f32), (-10., 120.), (10., 120.), (10., 60.)]);
+        assert_eq!(map32.to_svg(), svg);
+    }
+
+    #[test]
+    fn test_graticule() {
+        let mut map = Map::new(100, 0., 0.);
+        map.set_graticule(30., Style::new().stroke("grey", 0.5));
+        let svg = map.to_svg();
+        let d = svg.split("<path d=\"").nth(1).unwrap().split('"').next().unwrap();
+        // 7 meridians, incl. the 2 on the edge, & 5 parallels
+        assert_eq!(d.matches('M').count(), 7 + 5);
+        assert!(svg.contains("fill=\"none\" stroke=\"grey\" stroke-width=\"0.5\""));
+
+        // Lines go out from the equator & prime meridian, even when the step doesn't divide 90
+        let mut map = Map::new(100, 0., 0.);
+        map.set_graticule(40., Style::new().stroke("grey", 0.5));
+        let svg = map.to_svg();
+        let d = svg.split("<path d=\"").nth(1).unwrap().split('"').next().unwrap();
+        // Meridians & parallels at 0, ±40 & ±80
+        assert_eq!(d.matches('M').count(), 5 + 5);
+        assert!(d.contains("M50 100L50 99.998"), "{}", d);
+        assert!(d.contains("M0 50L"), "{}", d);
+
+        let map = map.with_radius(10.).with_offset(-20., 0.);
+        assert_eq!(map.xy_for_pos(0., 0.), Some((30., 50.)));
+        let (x, y) = map.with_rotation(90.).xy_for_pos(0., 90.).unwrap();
+        assert!((x - 30.).abs() < 1e-9 && (y - 40.).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_negative_radius() {
+        Map::new(100, 0., 0.).with_radius(-40.);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_zero_radius() {
+        Map::new(100, 0., 0.).with_radius(0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_nan_radius() {
+        Map::new(100, 0., 0.).with_radius(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "radius must be more than 0")]
+    fn test_infinite_radius() {
+        Map::new(100, 0., 0.).with_radius(f64::INFINITY);
+    }
+
+    #[test]
+    fn test_graticule_same_as_raster() {
+        for &(lat, lon, step) in &[(0., 0., 40.), (41.89889, 12.47337, 15.), (-60., 100., 25.)] {
+            let map = Map::new(200, lat, lon);
+            let mut o = OrthoProj::new(200, lat, lon, 0u8);
+            o.draw_graticule(step, step, 1);
+            // Every point of every SVG line is on a line in the image
+            for path in map.graticule_paths(step) {
+                for &(x, y) in &path {
+                    let (x, y) = (pixel_floor(x), pixel_floor(y));
+                    if (0. ..200.).contains(&x) && (0. ..200.).contains(&y) {
+                        assert_eq!(o.get_pixel(x as u32, y as u32), &1, "{} ({}, {})", step, x, y);
+                    }
+                }
+            }
+        }
+    }
+}
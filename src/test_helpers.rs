@@ -0,0 +1,30 @@
+//! Helpers shared by the tests in different modules
+
+use {OrthoProj, Projection};
+
+/// How many pixels are set to `value`
+pub fn count<P: Projection>(o: &OrthoProj<u8, P>, value: u8) -> usize {
+    o.iter().filter(|&&v| v == value).count()
+}
+
+/// Are `a` & `b` the same point on a map, give or take rounding
+pub fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+}
+
+/// `forward` then `inverse` should be the same point, and `inverse` of anything off the map
+/// should be `None`. Points which aren't on the map are fine.
+pub fn check_round_trip<P: Projection>(proj: P, lat: f64, lon: f64) -> Result<(), String> {
+    let (x, y) = match proj.forward(lat, lon) {
+        None => return Ok(()),
+        Some(xy) => xy,
+    };
+    let (lat2, lon2) = proj.inverse(x, y).ok_or_else(|| format!("{} {} -> {} {} has no inverse", lat, lon, x, y))?;
+    let dlon = (lon - lon2).rem_euclid(360.);
+    // Near the poles, lon doesn't matter much
+    let lon_ok = !(1e-6..=360. - 1e-6).contains(&dlon) || lat.abs() > 89.9999;
+    if (lat - lat2).abs() > 1e-6 || !lon_ok {
+        return Err(format!("{} {} -> {} {} -> {} {}", lat, lon, x, y, lat2, lon2));
+    }
+    Ok(())
+}
@@ -0,0 +1,442 @@
+//! Projections of the whole world, centred on a meridian and cut along the one opposite it
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use Float;
+use equirectangular::catmull_rom;
+use projection::{Boundary, Projection};
+
+/// How far `lon` is east of `lon0` (both in degrees), in radians, from -π to π.
+fn delta_lon(lon0: f64, lon: f64) -> f64 {
+    (lon - lon0 + 180.).rem_euclid(360.).to_radians() - PI
+}
+
+/// The longitude (in degrees, -180 to 180) which is `lambda` radians east of `lon0`.
+fn lon_for(lon0: f64, lambda: f64) -> f64 {
+    (lon0 + lambda.to_degrees() + 180.).rem_euclid(360.) - 180.
+}
+
+/// The equirectangular (plate carrée) projection, where the map is just the lat & lon, in
+/// radians. It's twice as wide as it is tall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlateCarree {
+    lon0: f64,
+}
+
+impl PlateCarree {
+    /// The equirectangular projection centred on the meridian `lon` (in degrees)
+    pub fn new<F: Float>(lon: F) -> Self {
+        PlateCarree{ lon0: lon.to_f64() }
+    }
+}
+
+impl Projection for PlateCarree {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat.abs() > 90. {
+            return None;
+        }
+        Some((delta_lon(self.lon0, lon), lat.to_radians()))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if x.abs() > PI || y.abs() > FRAC_PI_2 {
+            return None;
+        }
+        Some((y.to_degrees(), lon_for(self.lon0, x)))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        (0., self.lon0)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Antimeridian(90.)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (PI, FRAC_PI_2)
+    }
+}
+
+/// The spherical Mercator projection, as used by web maps (EPSG:3857). The poles are infinitely
+/// far away, so like web maps, it stops at 85.05113°, which makes the map square.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WebMercator {
+    lon0: f64,
+}
+
+impl WebMercator {
+    /// The northern & southern edge of the map, in degrees
+    pub const MAX_LAT: f64 = 85.0511287798066;
+
+    /// The Web Mercator projection centred on the meridian `lon` (in degrees)
+    pub fn new<F: Float>(lon: F) -> Self {
+        WebMercator{ lon0: lon.to_f64() }
+    }
+}
+
+impl Projection for WebMercator {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat.abs() > Self::MAX_LAT {
+            return None;
+        }
+        let y = (FRAC_PI_4 + lat.to_radians() / 2.).tan().ln();
+        Some((delta_lon(self.lon0, lon), y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if x.abs() > PI || y.abs() > PI {
+            return None;
+        }
+        let lat = 2. * y.exp().atan() - FRAC_PI_2;
+        Some((lat.to_degrees(), lon_for(self.lon0, x)))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        (0., self.lon0)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Antimeridian(Self::MAX_LAT)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (PI, PI)
+    }
+}
+
+/// The Mollweide projection, an equal-area map of the world in an ellipse twice as wide as it is
+/// tall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mollweide {
+    lon0: f64,
+}
+
+impl Mollweide {
+    /// The Mollweide projection centred on the meridian `lon` (in degrees)
+    pub fn new<F: Float>(lon: F) -> Self {
+        Mollweide{ lon0: lon.to_f64() }
+    }
+}
+
+impl Projection for Mollweide {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat.abs() > 90. {
+            return None;
+        }
+        let lambda = delta_lon(self.lon0, lon);
+        let lat = lat.to_radians();
+
+        // Solve 2θ + sin(2θ) = π sin(lat) for θ with Newton's method. This converges slowly near
+        // the poles, but there θ is lat anyway.
+        let target = PI * lat.sin();
+        let mut theta = lat;
+        for _ in 0..50 {
+            let f = 2. * theta + (2. * theta).sin() - target;
+            let df = 2. + 2. * (2. * theta).cos();
+            if df.abs() < 1e-12 {
+                break;
+            }
+            let step = f / df;
+            theta -= step;
+            if step.abs() < 1e-14 {
+                break;
+            }
+        }
+
+        let x = 2. * 2f64.sqrt() / PI * lambda * theta.cos();
+        let y = 2f64.sqrt() * theta.sin();
+        Some((x, y))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let sqrt2 = 2f64.sqrt();
+        if y.abs() > sqrt2 {
+            return None;
+        }
+        let theta = (y / sqrt2).asin();
+        let lat = ((2. * theta + (2. * theta).sin()) / PI).clamp(-1., 1.).asin();
+        let lambda = if x == 0. { 0. } else { PI * x / (2. * sqrt2 * theta.cos()) };
+        if lambda.abs() > PI {
+            return None;
+        }
+        Some((lat.to_degrees(), lon_for(self.lon0, lambda)))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        (0., self.lon0)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Antimeridian(90.)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (2. * 2f64.sqrt(), 2f64.sqrt())
+    }
+}
+
+/// Robinson's table of the length of each parallel (X), and how far north it is (Y), every 5° of
+/// latitude from the equator.
+const ROBINSON: [(f64, f64); 19] = [
+    (1.0000, 0.0000), (0.9986, 0.0620), (0.9954, 0.1240), (0.9900, 0.1860), (0.9822, 0.2480),
+    (0.9730, 0.3100), (0.9600, 0.3720), (0.9427, 0.4340), (0.9216, 0.4958), (0.8962, 0.5571),
+    (0.8679, 0.6176), (0.8350, 0.6769), (0.7986, 0.7346), (0.7597, 0.7903), (0.7186, 0.8435),
+    (0.6732, 0.8936), (0.6213, 0.9394), (0.5722, 0.9761), (0.5322, 1.0000),
+];
+
+/// The Robinson projection, a compromise world map which doesn't stretch things too much
+/// anywhere. It's defined by a table, which is smoothly interpolated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Robinson {
+    lon0: f64,
+}
+
+impl Robinson {
+    /// The Robinson projection centred on the meridian `lon` (in degrees)
+    pub fn new<F: Float>(lon: F) -> Self {
+        Robinson{ lon0: lon.to_f64() }
+    }
+
+    /// X & Y from the table, for `lat` (in degrees, 0 to 90), with a (Catmull-Rom) cubic curve
+    /// through the table values.
+    fn table(lat: f64) -> (f64, f64) {
+        let i = ((lat / 5.).floor() as usize).min(ROBINSON.len() - 2);
+        let t = lat / 5. - i as f64;
+        // Past the ends of the table, it's mirrored at the equator, and carries on in a straight
+        // line at the pole
+        let at = |j: i64| -> (f64, f64) {
+            let last = ROBINSON.len() as i64 - 1;
+            if j < 0 {
+                let (x, y) = ROBINSON[(-j) as usize];
+                (x, -y)
+            } else if j > last {
+                let (a, b) = (ROBINSON[last as usize], ROBINSON[last as usize - 1]);
+                (2. * a.0 - b.0, 2. * a.1 - b.1)
+            } else {
+                ROBINSON[j as usize]
+            }
+        };
+        let mut result = (0., 0.);
+        for (k, w) in catmull_rom(t).iter().enumerate() {
+            let v = at(i as i64 + k as i64 - 1);
+            result.0 += w * v.0;
+            result.1 += w * v.1;
+        }
+        result
+    }
+}
+
+impl Projection for Robinson {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat.abs() > 90. {
+            return None;
+        }
+        let (x, y) = Self::table(lat.abs());
+        Some((0.8487 * x * delta_lon(self.lon0, lon), 1.3523 * y * lat.signum()))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let y_table = y.abs() / 1.3523;
+        if y_table > 1. {
+            return None;
+        }
+        // Y goes up with lat, so find lat by bisection
+        let (mut low, mut high) = (0., 90.);
+        for _ in 0..60 {
+            let mid = (low + high) / 2.;
+            if Self::table(mid).1 < y_table {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let lat = (low + high) / 2.;
+        let lambda = x / (0.8487 * Self::table(lat).0);
+        if lambda.abs() > PI {
+            return None;
+        }
+        Some((lat * y.signum(), lon_for(self.lon0, lambda)))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        (0., self.lon0)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Antimeridian(90.)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (0.8487 * PI, 1.3523)
+    }
+}
+
+/// The Winkel tripel projection, a compromise world map, used by National Geographic.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WinkelTripel {
+    lon0: f64,
+}
+
+impl WinkelTripel {
+    /// The Winkel tripel projection centred on the meridian `lon` (in degrees)
+    pub fn new<F: Float>(lon: F) -> Self {
+        WinkelTripel{ lon0: lon.to_f64() }
+    }
+
+    /// The projection, for `lat` & `lambda` (east of the centre) in radians
+    fn project(lat: f64, lambda: f64) -> (f64, f64) {
+        // cos(lat1), where lat1 = acos(2/π) is the standard parallel
+        let cos_lat1 = 2. / PI;
+        let alpha = (lat.cos() * (lambda / 2.).cos()).acos();
+        // 1 / sinc(alpha)
+        let inv_sinc = if alpha == 0. { 1. } else { alpha / alpha.sin() };
+        let x = (lambda * cos_lat1 + 2. * lat.cos() * (lambda / 2.).sin() * inv_sinc) / 2.;
+        let y = (lat + lat.sin() * inv_sinc) / 2.;
+        (x, y)
+    }
+}
+
+impl Projection for WinkelTripel {
+    fn forward(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        if lat.abs() > 90. {
+            return None;
+        }
+        Some(Self::project(lat.to_radians(), delta_lon(self.lon0, lon)))
+    }
+
+    fn inverse(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (extent_x, extent_y) = self.extent();
+        if x.abs() > extent_x || y.abs() > extent_y {
+            return None;
+        }
+        // There's no formula for the inverse, so use Newton's method, with the Jacobian found
+        // numerically. Starting from a little inside the map, it quickly gets there.
+        let (mut lat, mut lambda) = (y * FRAC_PI_2 / extent_y * 0.9, x * PI / extent_x * 0.9);
+        let h = 1e-7;
+        for _ in 0..100 {
+            let (fx, fy) = Self::project(lat, lambda);
+            let (ex, ey) = (fx - x, fy - y);
+            if ex.abs() < 1e-13 && ey.abs() < 1e-13 {
+                break;
+            }
+            let (dx_dlat, dy_dlat) = {
+                let (a, b) = Self::project(lat + h, lambda);
+                let (c, d) = Self::project(lat - h, lambda);
+                ((a - c) / (2. * h), (b - d) / (2. * h))
+            };
+            let (dx_dlambda, dy_dlambda) = {
+                let (a, b) = Self::project(lat, lambda + h);
+                let (c, d) = Self::project(lat, lambda - h);
+                ((a - c) / (2. * h), (b - d) / (2. * h))
+            };
+            let det = dx_dlat * dy_dlambda - dx_dlambda * dy_dlat;
+            if det.abs() < 1e-15 {
+                break;
+            }
+            lat = (lat - (ex * dy_dlambda - ey * dx_dlambda) / det).clamp(-FRAC_PI_2, FRAC_PI_2);
+            lambda -= (ey * dx_dlat - ex * dy_dlat) / det;
+        }
+
+        // Past the edge of the map, the nearest point on the map isn't at x, y
+        let (fx, fy) = Self::project(lat, lambda);
+        if lambda.abs() > PI || (fx - x).abs() > 1e-9 || (fy - y).abs() > 1e-9 {
+            return None;
+        }
+        Some((lat.to_degrees(), lon_for(self.lon0, lambda)))
+    }
+
+    fn centre(&self) -> (f64, f64) {
+        (0., self.lon0)
+    }
+
+    fn boundary(&self) -> Boundary {
+        Boundary::Antimeridian(90.)
+    }
+
+    fn extent(&self) -> (f64, f64) {
+        (Self::project(0., PI).0, Self::project(FRAC_PI_2, 0.).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::{check_round_trip, close};
+
+    #[test]
+    fn test_forward() {
+        assert!(close(PlateCarree::new(0.).forward(45., -90.).unwrap(), (-FRAC_PI_2, FRAC_PI_4)));
+        assert!(close(PlateCarree::new(170.).forward(0., -170.).unwrap(), (20f64.to_radians(), 0.)));
+
+        let mercator = WebMercator::new(0.);
+        assert!(close(mercator.forward(WebMercator::MAX_LAT, 180.).unwrap(), (-PI, PI)));
+        assert!(close(mercator.forward(45., 0.).unwrap(), (0., 0.881373587)));
+        assert_eq!(mercator.forward(86., 0.), None);
+
+        let mollweide = Mollweide::new(0.);
+        assert!(close(mollweide.forward(90., 0.).unwrap(), (0., 2f64.sqrt())));
+        assert!(close(mollweide.forward(0., 90.).unwrap(), (2f64.sqrt(), 0.)));
+
+        // At 45°, Robinson's table is used as it is
+        assert!(close(Robinson::new(0.).forward(45., 45.).unwrap(), (0.8487 * 0.8962 * FRAC_PI_4, 1.3523 * 0.5571)));
+        assert!(close(Robinson::new(0.).forward(-45., -45.).unwrap(), (-0.8487 * 0.8962 * FRAC_PI_4, -1.3523 * 0.5571)));
+
+        let winkel = WinkelTripel::new(0.);
+        assert!(close(winkel.forward(0., 180.).unwrap(), (-1. - FRAC_PI_2, 0.)));
+        assert!(close(winkel.forward(90., 0.).unwrap(), (0., FRAC_PI_2)));
+    }
+
+    #[test]
+    fn test_extent() {
+        fn check<P: Projection>(proj: P) {
+            let (w, h) = proj.extent();
+            let (x, _) = proj.forward(0., proj.centre().1 + 180.).unwrap();
+            let (_, y) = proj.forward(90., proj.centre().1).unwrap();
+            assert!((x.abs() - w).abs() < 1e-9 && (y - h).abs() < 1e-9);
+            assert!(proj.inverse(w * 1.01, 0.).is_none());
+            assert!(proj.inverse(0., h * 1.01).is_none());
+        }
+        check(PlateCarree::new(20.));
+        check(Mollweide::new(20.));
+        check(Robinson::new(20.));
+        check(WinkelTripel::new(20.));
+        let mercator = WebMercator::new(20.);
+        assert_eq!(mercator.extent(), (PI, PI));
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips(lon0 in -180f64..180., lat in -90f64..90., lon in -180f64..180.) {
+            /// Everything is on a world map, so it always goes there & back
+            fn check<P: Projection>(proj: P, lat: f64, lon: f64) {
+                assert!(proj.forward(lat, lon).is_some(), "{} {} isn't on the map", lat, lon);
+                check_round_trip(proj, lat, lon).unwrap();
+            }
+            check(PlateCarree::new(lon0), lat, lon);
+            if lat.abs() < WebMercator::MAX_LAT {
+                check(WebMercator::new(lon0), lat, lon);
+            }
+            check(Mollweide::new(lon0), lat, lon);
+            check(Robinson::new(lon0), lat, lon);
+            check(WinkelTripel::new(lon0), lat, lon);
+        }
+
+        #[test]
+        fn inverse_is_on_the_map(x in -4f64..4., y in -4f64..4.) {
+            fn check<P: Projection>(proj: P, x: f64, y: f64) {
+                if let Some((lat, lon)) = proj.inverse(x, y) {
+                    assert!((-90.0..=90.).contains(&lat) && (-180.0..=180.).contains(&lon));
+                    let (x2, y2) = proj.forward(lat, lon).unwrap();
+                    // The left & right edges are the same meridian
+                    assert!(((x2 - x).abs() < 1e-6 || (x2.abs() - x.abs()).abs() < 1e-6) && (y2 - y).abs() < 1e-6, "{} {} -> {} {} -> {} {}", x, y, lat, lon, x2, y2);
+                }
+            }
+            check(PlateCarree::new(10.), x, y);
+            check(WebMercator::new(10.), x, y);
+            check(Mollweide::new(10.), x, y);
+            check(Robinson::new(10.), x, y);
+            check(WinkelTripel::new(10.), x, y);
+        }
+    }
+}